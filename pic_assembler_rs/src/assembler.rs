@@ -1,131 +1,422 @@
-// use pic_emulator_rs::nbitnumber;
-// use pic_emulator_rs::pic;
-//
-// use std::collections::HashMap;
-// use pic_emulator_rs::nbitnumber::{NBitNumber, u12};
-// use pic_emulator_rs::pic::PIC10F200;
-//
-//
-// pub enum PICCategory {
-//     Miscellaneous,
-//     BitOperation,
-//     ControlTransfer,
-//     OperationsWithW,
-//     ALUOperation,
-// }
-//
-// pub struct Instruction<const N: usize>  {
-//     pub mnemonic: String,
-//     pub category: PICCategory,
-//     pub opcode: u12,
-//     pub operand1_length: NBitNumber<N>,
-// }
-//
-// //create implementation for the INSTRUCTION set
-// impl<const N: usize>  Instruction<N> {
-//     pub fn new(mnemonic: String, category: PICCategory, opcode: u12, operand1_length: NBitNumber<N>) -> Instruction<N> {
-//         Instruction {
-//             mnemonic,
-//             category,
-//             opcode,
-//             operand1_length,
-//         }
-//     }
-// }
-//
-//
-//
-// pub const INSTRUCTION_TABLE: HashMap<&str, u16> = [
-//     //Miscellaneous Instructions
-//     ("NOP", 0x000),
-//     ("OPTION", 0x002),
-//     ("SLEEP", 0x003),
-//     ("CLRWDT", 0x004),
-//     ("TRIS", 0x004),
-//     ("MOVLB", 0x010),
-//     ("RETURN", 0x01E),
-//     ("RETFIE", 0x01F),
-//
-//     //ALU Operations
-//     ("MOVWF", 0x001 << 0x5),
-//     ("CLR", 0x001 << 0x6),
-//     ("SUBWF", 0x002 << 0x6),
-//     ("DECF", 0x003 << 0x6),
-//     ("IORWF", 0x004 << 0x6),
-//     ("ANDWF", 0x005 << 0x6),
-//     ("XORWF", 0x006 << 0x6),
-//     ("ADDWF", 0x007 << 0x6),
-//     ("MOVF", 0x008 << 0x6),
-//     ("COMF", 0x009 << 0x6),
-//     ("INCF", 0x00A << 0x6),
-//     ("DECFSZ", 0x00B << 0x6),
-//     ("RRF", 0x00C << 0x6),
-//     ("RLF", 0x00D << 0x6),
-//     ("SWAPF", 0x00E << 0x6),
-//     ("INCFSZ", 0x00F << 0x6),
-//
-//     //Bit Operations
-//     ("BCF", 0x400),
-//     ("BSF", 0x500),
-//     ("BTFSC", 0x600),
-//     ("BTFSS", 0x700),
-//
-//     //Control Transfer
-//     ("RETLW", 0x800),
-//     ("CALL", 0x900),
-//     ("GOTO", 0xA00),
-// ].iter().cloned().collect();
-//
-// //RETURN THE LENGTH OF THE FIRST OPERAND in bits
-// // pub const OPERAND_TABLE: HashMap <&str, u8>
-//
-// // pub fn assemble_pic_file(pic_file: &str) -> ([u12 ; 0x200]) {
-// //     //let pic_file = read_pic(pic_file);
-// //     let mut pic_program_data = [u12 ; 0x200];
-// //
-// //     for line in pic_file {
-// //         //Translate the line of PIC code into machine code and save it to the array
-// //         let machine_code = encode(line);
-// //         pic_program_data.push(machine_code);
-// //     }
-// //
-// //     // Return the machine code
-// //     return pic_program_data;
-// // }
-// //
-// // //Translate a line of PIC code into machine code
-// // pub fn encode(pic_line : &str) -> u12 {
-// //     let PNEUMONIC = pic_line[0];
-// //     let OPERAND1 = pic_line[1];
-// //     let OPERAND2 = pic_line[2];
-// //
-// //     //get the opcode from the instruction table
-// //     let opcode = INSTRUCTION_TABLE[PNEUMONIC];
-// //
-// //     //Add the first operand to the opcode
-// //     let machine_code = opcode + OPERAND1;
-// //
-// //     //Add the second operand to the opcode
-// //     //the second operand should be shifted left by the length of the first operand
-// //     machine_code = machine_code + OPERAND2 << 0;
-// //
-// //     //Return the machine code
-// // }
-// //
-// // // pub fn read_pic(pic_file: &str) -> ([str ; 0x200]){
-// // //     //Split the PIC code to 2D array of strings, deliminated lines and spaces
-// // //     program_line : [str ; 0x200];
-// // //     for line in pic_file {
-// // //         program_line.push(line);
-// // //     }
-// //
-// // //     return pic_str;
-// // // }
-// //
-// // pub fn program_pic(pic_file: &str, pic: PIC10F200) {
-// //     // //Generate the machine code
-// //     // assemble_picry_file(pic_file);
-// //     //
-// //     // //Upload the machine code to the PIC with flash()
-// //     // pic.program_chip();
-// // }
\ No newline at end of file
+use std::collections::HashMap;
+use std::fmt;
+
+use pic_emulator_rs::disassembler::SPECIAL_REGISTER_NAMES;
+use pic_emulator_rs::nbitnumber::u12;
+use pic_emulator_rs::opcodes::{align_opcode, OPCODES};
+use pic_emulator_rs::pic::{Cpu, PICInstructionMnemonic};
+
+pub use pic_emulator_rs::loader::PROGRAM_WORDS;
+
+//Where a line of source came from, file is None for the source given to the assembler
+//and the name passed to INCLUDE for an included one, line numbers start at 1
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Location {
+    pub file: Option<String>,
+    pub line: usize,
+}
+
+impl fmt::Display for Location {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.file {
+            Some(file) => write!(f, "{} line {}", file, self.line),
+            None => write!(f, "line {}", self.line),
+        }
+    }
+}
+
+//Reasons a source file could not be assembled
+#[derive(Debug, PartialEq, Eq)]
+pub enum AssemblerError {
+    UnknownMnemonic { at: Location, mnemonic: String },
+    InvalidOperand { at: Location, operand: String },
+    WrongOperandCount { at: Location, expected: usize, found: usize },
+    UndefinedSymbol { at: Location, symbol: String },
+    OutOfRange { at: Location, value: u16, max: u16 },
+    ProgramTooLarge { at: Location, address: u16 },
+    MissingInclude { at: Location, file: String },
+    RecursiveInclude { at: Location, file: String },
+}
+
+impl fmt::Display for AssemblerError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AssemblerError::UnknownMnemonic { at, mnemonic } => write!(f, "{}: unknown mnemonic {}", at, mnemonic),
+            AssemblerError::InvalidOperand { at, operand } => write!(f, "{}: invalid operand {}", at, operand),
+            AssemblerError::WrongOperandCount { at, expected, found } => {
+                write!(f, "{}: expected {} operands, found {}", at, expected, found)
+            }
+            AssemblerError::UndefinedSymbol { at, symbol } => write!(f, "{}: undefined symbol {}", at, symbol),
+            AssemblerError::OutOfRange { at, value, max } => write!(f, "{}: 0x{:X} is larger than 0x{:X}", at, value, max),
+            AssemblerError::ProgramTooLarge { at, address } => write!(f, "{}: address 0x{:03X} is past the end of program memory", at, address),
+            AssemblerError::MissingInclude { at, file } => write!(f, "{}: cannot include {}", at, file),
+            AssemblerError::RecursiveInclude { at, file } => write!(f, "{}: {} includes itself", at, file),
+        }
+    }
+}
+
+//An assembled program and, for every word placed, the file and line it came from
+pub struct AsmOutput {
+    pub words: [u12; PROGRAM_WORDS],
+    pub line_map: HashMap<u16, Location>,
+}
+
+impl AsmOutput {
+    //source line of the instruction at a program address, the PC during debugging
+    pub fn source_line(&self, address: u16) -> Option<&Location> {
+        self.line_map.get(&address)
+    }
+}
+
+//One line of source split into its fields, comments already removed
+struct SourceLine {
+    at: Location,
+    label: Option<String>,
+    mnemonic: Option<String>,
+    operands: Vec<String>,
+}
+
+//Assemble a whole source file into a program image for Programmable::program_chip
+//Labels start in the first column (the trailing colon is optional), EQU defines a
+//symbol, ORG moves the location counter and END stops assembly
+//DW places each operand as a raw word and DT places each as a RETLW, for lookup tables
+//byte instructions written without a destination store to the file register (d = 1)
+pub fn assemble(source: &str) -> Result<[u12; PROGRAM_WORDS], AssemblerError> {
+    assemble_with_lines(source).map(|output| output.words)
+}
+
+//Assemble and program a core, keeping the line map for source level debugging
+pub fn load_asm(cpu: &mut dyn Cpu, source: &str) -> Result<AsmOutput, AssemblerError> {
+    let output = assemble_with_lines(source)?;
+    cpu.program_chip(output.words);
+    Ok(output)
+}
+
+pub fn assemble_with_lines(source: &str) -> Result<AsmOutput, AssemblerError> {
+    assemble_with_includes(source, |_| None)
+}
+
+//INCLUDE "file" (or #include) splices in the source include returns for the name, so every
+//file shares one symbol table and location counter
+//errors and the line map name the file a line came from and count lines within it
+pub fn assemble_with_includes(source: &str, include: impl Fn(&str) -> Option<String>) -> Result<AsmOutput, AssemblerError> {
+    let lines = expand_includes(parse_lines(source, None), &include, &mut Vec::new())?;
+    let symbols = collect_symbols(&lines)?;
+
+    let mut program = [u12::new(0); PROGRAM_WORDS];
+    let mut line_map = HashMap::new();
+    let mut address = 0;
+    for line in &lines {
+        let Some(mnemonic) = &line.mnemonic else {
+            continue;
+        };
+        match mnemonic.as_str() {
+            "EQU" => continue,
+            "END" => break,
+            "ORG" => {
+                address = origin(line, &symbols)?;
+                continue;
+            }
+            _ => {}
+        }
+        for word in encode_words(line, mnemonic, &symbols)? {
+            if address >= PROGRAM_WORDS {
+                return Err(AssemblerError::ProgramTooLarge { at: line.at.clone(), address: address as u16 });
+            }
+            program[address] = u12::new(word);
+            line_map.insert(address as u16, line.at.clone());
+            address += 1;
+        }
+    }
+    Ok(AsmOutput { words: program, line_map })
+}
+
+fn parse_lines(source: &str, file: Option<&str>) -> Vec<SourceLine> {
+    let mut lines = Vec::new();
+    for (index, text) in source.lines().enumerate() {
+        let code = strip_comment(text);
+        let mut rest = code.trim();
+        if rest.is_empty() {
+            continue;
+        }
+
+        //a label is anything in the first column, or any first field ending with a colon
+        let mut label = None;
+        let first = rest.split_whitespace().next().unwrap_or("");
+        let in_first_column = !code.starts_with(char::is_whitespace);
+        if first.ends_with(':') || (in_first_column && !is_mnemonic(first)) {
+            label = Some(first.trim_end_matches(':').to_string());
+            rest = rest[first.len()..].trim();
+        }
+
+        let (mnemonic, operands) = match rest.split_once(char::is_whitespace) {
+            Some((mnemonic, operands)) => (mnemonic, operands.trim()),
+            None => (rest, ""),
+        };
+        let operands = if operands.is_empty() {
+            Vec::new()
+        } else {
+            split_operands(operands)
+        };
+
+        lines.push(SourceLine {
+            at: Location { file: file.map(str::to_string), line: index + 1 },
+            label,
+            mnemonic: (!mnemonic.is_empty()).then(|| mnemonic.to_uppercase()),
+            operands,
+        });
+    }
+    lines
+}
+
+fn expand_includes(
+    lines: Vec<SourceLine>,
+    include: &impl Fn(&str) -> Option<String>,
+    including: &mut Vec<String>,
+) -> Result<Vec<SourceLine>, AssemblerError> {
+    let mut expanded = Vec::new();
+    for line in lines {
+        if !matches!(line.mnemonic.as_deref(), Some("INCLUDE" | "#INCLUDE")) {
+            expanded.push(line);
+            continue;
+        }
+        let file = single_operand(&line)?.trim_matches(|c| matches!(c, '"' | '<' | '>')).to_string();
+        if including.contains(&file) {
+            return Err(AssemblerError::RecursiveInclude { at: line.at.clone(), file });
+        }
+        let Some(source) = include(&file) else {
+            return Err(AssemblerError::MissingInclude { at: line.at.clone(), file });
+        };
+        let lines = parse_lines(&source, Some(&file));
+        including.push(file);
+        expanded.extend(expand_includes(lines, include, including)?);
+        including.pop();
+    }
+    Ok(expanded)
+}
+
+//a semicolon starts a comment unless it is inside a character literal
+fn strip_comment(text: &str) -> &str {
+    let mut quoted = false;
+    for (index, c) in text.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ';' if !quoted => return &text[..index],
+            _ => {}
+        }
+    }
+    text
+}
+
+fn split_operands(operands: &str) -> Vec<String> {
+    let mut split = Vec::new();
+    let mut quoted = false;
+    let mut start = 0;
+    for (index, c) in operands.char_indices() {
+        match c {
+            '\'' => quoted = !quoted,
+            ',' if !quoted => {
+                split.push(operands[start..index].trim().to_string());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    split.push(operands[start..].trim().to_string());
+    split
+}
+
+fn is_mnemonic(field: &str) -> bool {
+    let field = field.to_uppercase();
+    matches!(field.as_str(), "EQU" | "ORG" | "END" | "DW" | "DT" | "INCLUDE" | "#INCLUDE" | "CLRW" | "CLRF")
+        || OPCODES.iter().any(|entry| entry.name == field && entry.mnemonic != PICInstructionMnemonic::CLR)
+}
+
+//first pass, labels take the address of the next instruction
+fn collect_symbols(lines: &[SourceLine]) -> Result<HashMap<String, u16>, AssemblerError> {
+    let mut symbols = HashMap::new();
+    let mut address = 0;
+    for line in lines {
+        let mnemonic = line.mnemonic.as_deref();
+        match (mnemonic, &line.label) {
+            (Some("EQU"), Some(label)) => {
+                let value = single_operand(line)
+                    .and_then(|operand| resolve(operand, &symbols, &line.at))?;
+                symbols.insert(label.clone(), value);
+                continue;
+            }
+            (Some("EQU"), None) => {
+                return Err(AssemblerError::InvalidOperand { at: line.at.clone(), operand: "EQU".to_string() });
+            }
+            (_, Some(label)) => {
+                symbols.insert(label.clone(), address as u16);
+            }
+            _ => {}
+        }
+        match mnemonic {
+            Some("END") => break,
+            Some("ORG") => address = origin(line, &symbols)?,
+            Some("DW" | "DT") => address += line.operands.len(),
+            Some(_) => address += 1,
+            None => {}
+        }
+    }
+    Ok(symbols)
+}
+
+fn origin(line: &SourceLine, symbols: &HashMap<String, u16>) -> Result<usize, AssemblerError> {
+    let address = single_operand(line).and_then(|operand| resolve(operand, symbols, &line.at))?;
+    Ok(address as usize)
+}
+
+fn single_operand(line: &SourceLine) -> Result<&str, AssemblerError> {
+    match line.operands.as_slice() {
+        [operand] => Ok(operand),
+        operands => Err(AssemblerError::WrongOperandCount { at: line.at.clone(), expected: 1, found: operands.len() }),
+    }
+}
+
+//numbers are 0x prefixed hex, 0b prefixed binary, decimal or an ASCII character in single quotes
+//used for every operand, EQU and ORG included
+pub fn parse_number(text: &str) -> Option<u16> {
+    if let Some(hex) = text.strip_prefix("0x").or_else(|| text.strip_prefix("0X")) {
+        return u16::from_str_radix(hex, 16).ok();
+    }
+    if let Some(binary) = text.strip_prefix("0b").or_else(|| text.strip_prefix("0B")) {
+        return u16::from_str_radix(binary, 2).ok();
+    }
+    if let Some(character) = text.strip_prefix('\'').and_then(|rest| rest.strip_suffix('\'')) {
+        let mut chars = character.chars();
+        return match (chars.next(), chars.next()) {
+            (Some(c), None) if c.is_ascii() => Some(c as u16),
+            _ => None,
+        };
+    }
+    text.parse().ok()
+}
+
+//an operand is a number, a user symbol or the name of a special purpose register
+fn resolve(operand: &str, symbols: &HashMap<String, u16>, at: &Location) -> Result<u16, AssemblerError> {
+    if let Some(value) = parse_number(operand) {
+        return Ok(value);
+    }
+    if let Some(value) = symbols.get(operand) {
+        return Ok(*value);
+    }
+    if let Some(address) = SPECIAL_REGISTER_NAMES.iter().position(|name| name.eq_ignore_ascii_case(operand)) {
+        return Ok(address as u16);
+    }
+    if operand.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        return Err(AssemblerError::UndefinedSymbol { at: at.clone(), symbol: operand.to_string() });
+    }
+    Err(AssemblerError::InvalidOperand { at: at.clone(), operand: operand.to_string() })
+}
+
+fn ranged(value: u16, max: u16, at: &Location) -> Result<u16, AssemblerError> {
+    if value > max {
+        return Err(AssemblerError::OutOfRange { at: at.clone(), value, max });
+    }
+    Ok(value)
+}
+
+fn destination(operand: &str, at: &Location) -> Result<u16, AssemblerError> {
+    match operand {
+        "W" | "w" | "0" => Ok(0),
+        "F" | "f" | "1" => Ok(1),
+        _ => Err(AssemblerError::InvalidOperand { at: at.clone(), operand: operand.to_string() }),
+    }
+}
+
+//the words a line places in program memory, one per operand for the data directives
+fn encode_words(line: &SourceLine, mnemonic: &str, symbols: &HashMap<String, u16>) -> Result<Vec<u16>, AssemblerError> {
+    let data = |max: u16| {
+        line.operands
+            .iter()
+            .map(|operand| resolve(operand, symbols, &line.at).and_then(|value| ranged(value, max, &line.at)))
+            .collect()
+    };
+    match mnemonic {
+        "DW" => data(0xFFF),
+        //RETLW k
+        "DT" => data(0xFF).map(|values: Vec<u16>| values.into_iter().map(|k| 0x800 | k).collect()),
+        _ => Ok(vec![encode_line(line, mnemonic, symbols)?]),
+    }
+}
+
+//second pass, the fixed bits come from OPCODES and the operands fill the low bits
+fn encode_line(line: &SourceLine, mnemonic: &str, symbols: &HashMap<String, u16>) -> Result<u16, AssemblerError> {
+    use PICInstructionMnemonic::*;
+
+    //CLR is written as CLRW or CLRF
+    let (name, implied_d) = match mnemonic {
+        "CLRW" => ("CLR", Some(0)),
+        "CLRF" => ("CLR", Some(1)),
+        "CLR" => ("", None),
+        name => (name, None),
+    };
+    let entry = OPCODES.iter().find(|entry| entry.name == name).ok_or_else(|| AssemblerError::UnknownMnemonic {
+        at: line.at.clone(),
+        mnemonic: mnemonic.to_string(),
+    })?;
+    let (opcode, _) = align_opcode(&entry.opcode);
+
+    let at = &line.at;
+    let operands = &line.operands;
+    let expect = |expected: usize| {
+        if operands.len() != expected {
+            return Err(AssemblerError::WrongOperandCount { at: at.clone(), expected, found: operands.len() });
+        }
+        Ok(())
+    };
+    let value = |index: usize, max: u16| resolve(&operands[index], symbols, at).and_then(|value| ranged(value, max, at));
+
+    let operand_bits = match entry.mnemonic {
+        NOP | OPTION | SLEEP | CLRWDT => {
+            expect(0)?;
+            0
+        }
+        TRIS => {
+            expect(1)?;
+            let f = value(0, 0x07)?;
+            if f < 0x05 {
+                return Err(AssemblerError::InvalidOperand { at: at.clone(), operand: operands[0].clone() });
+            }
+            f & 0x003
+        }
+        CLR if implied_d == Some(0) => {
+            expect(0)?;
+            0
+        }
+        MOVWF | CLR => {
+            expect(1)?;
+            implied_d.unwrap_or(0) << 5 | value(0, 0x1F)?
+        }
+        //as in MPASM a missing destination is F, so ADDWF f is ADDWF f, F
+        SUBWF | DECF | IORWF | ANDWF | XORWF | ADDWF | MOVF | COMF | INCF | DECFSZ | RRF | RLF | SWAPF | INCFSZ => {
+            let d = match operands.len() {
+                1 => 1,
+                _ => {
+                    expect(2)?;
+                    destination(&operands[1], at)?
+                }
+            };
+            d << 5 | value(0, 0x1F)?
+        }
+        BCF | BSF | BTFSC | BTFSS => {
+            expect(2)?;
+            value(1, 0x07)? << 5 | value(0, 0x1F)?
+        }
+        GOTO => {
+            expect(1)?;
+            value(0, 0x1FF)?
+        }
+        CALL | RETLW | MOVLW | IORLW | ANDLW | XORLW => {
+            expect(1)?;
+            value(0, 0xFF)?
+        }
+        MOVLB | RETURN | RETFIE | UND => {
+            return Err(AssemblerError::UnknownMnemonic { at: at.clone(), mnemonic: mnemonic.to_string() });
+        }
+    };
+    Ok(opcode | operand_bits)
+}
@@ -0,0 +1,3 @@
+pub mod assembler;
+// Tests module
+pub mod tests;
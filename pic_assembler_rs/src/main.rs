@@ -1,19 +1,19 @@
-mod assembler;
-mod tests;
-
-//take filename from the command line and pass it to the assembler
+//take filename from the command line, assemble it and write the program image
 use std::env;
-use std::path::Path;
+use std::fs;
+use std::path::{Path, PathBuf};
 use std::process;
 use std::string::String;
 use std::vec::Vec;
 
+use pic_assembler_rs::assembler::assemble_with_includes;
+
 
 fn main() {
     let args: Vec<String> = env::args().collect();
 
-    if args.len() != 2 || !args[1].ends_with(".pic") {
-        println!("❌ Usage: {} <filename.pic>", args[0]);
+    if !(2..=3).contains(&args.len()) || !args[1].ends_with(".pic") {
+        eprintln!("❌ Usage: {} <filename.pic> [output.bin]", args[0]);
         process::exit(1);
     }
 
@@ -21,6 +21,29 @@ fn main() {
     let path = Path::new(filename);
     let display = path.display();
 
-    
-}
+    let source = match fs::read_to_string(path) {
+        Ok(source) => source,
+        Err(error) => {
+            eprintln!("❌ Could not read {}: {}", display, error);
+            process::exit(1);
+        }
+    };
+    //included files are found relative to the file being assembled
+    let directory = path.parent().unwrap_or(Path::new("."));
+    let include = |file: &str| fs::read_to_string(directory.join(file)).ok();
+    let output = match assemble_with_includes(&source, include) {
+        Ok(output) => output,
+        Err(error) => {
+            eprintln!("❌ {}", error);
+            process::exit(1);
+        }
+    };
 
+    //a raw little endian image from address 0x000, the format load_file reads for anything but .hex
+    let destination = args.get(2).map(PathBuf::from).unwrap_or_else(|| path.with_extension("bin"));
+    let image: Vec<u8> = output.words.iter().flat_map(|word| word.get().to_le_bytes()).collect();
+    if let Err(error) = fs::write(&destination, image) {
+        eprintln!("❌ Could not write {}: {}", destination.display(), error);
+        process::exit(1);
+    }
+}
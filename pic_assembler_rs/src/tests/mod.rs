@@ -0,0 +1,45 @@
+// Assemble a program, run it from power on and check W and any file registers
+// cycles count from power on, so the instruction at 0x000 executes during cycle 3
+#[cfg(test)]
+macro_rules! pic_test {
+    { asm: $asm:expr, cycles: $cycles:expr, expect_w: $w:expr, expect: [$($address:expr => $value:expr),* $(,)?] $(,)? } => {{
+        use pic_emulator_rs::pic::{PipelinedTuringMachine, Programmable};
+
+        let program = crate::assembler::assemble($asm).expect("program should assemble");
+        let mut pic = pic_emulator_rs::pic::PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.run_cycles($cycles);
+
+        assert_eq!(pic.w_register(), $w, "W");
+        $(
+            let address: u16 = $address;
+            assert_eq!(pic.peek(pic_emulator_rs::nbitnumber::u5::new(address)), $value, "register 0x{:02X}", address);
+        )*
+    }};
+}
+
+// Assemble, disassemble every word and assemble the listing again, the two images must
+// match word for word, words the disassembler can only show as UND go back in with DW
+#[cfg(test)]
+pub fn assert_round_trip(source: &str) {
+    use pic_emulator_rs::disassembler::disassemble;
+
+    let words = crate::assembler::assemble(source).expect("program should assemble");
+    let listing: String = words
+        .iter()
+        .map(|word| match disassemble(*word, None) {
+            text if text.starts_with("UND") => format!("        DW 0x{:03X}\n", word.get()),
+            text => format!("        {}\n", text),
+        })
+        .collect();
+    let reassembled = crate::assembler::assemble(&listing).unwrap_or_else(|error| panic!("{}\n{}", error, listing));
+    for (address, (word, again)) in words.iter().zip(reassembled.iter()).enumerate() {
+        assert_eq!(word.get(), again.get(), "0x{:03X}: {}", address, disassemble(*word, None));
+    }
+}
+
+pub mod test_assembler;
+pub mod test_pic_test;
+pub mod test_blink;
+pub mod test_round_trip;
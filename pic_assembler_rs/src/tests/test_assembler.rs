@@ -0,0 +1,211 @@
+#[cfg(test)]
+mod test {
+    use crate::assembler::{assemble, load_asm, parse_number, AssemblerError, Location};
+    use pic_emulator_rs::nbitnumber::{u12, NumberOperations};
+
+    // a line of the source passed to the assembler itself
+    fn line(line: usize) -> Location {
+        Location { file: None, line }
+    }
+
+    fn in_file(file: &str, line: usize) -> Location {
+        Location { file: Some(file.to_string()), line }
+    }
+
+    fn words(source: &str, count: usize) -> Vec<u16> {
+        assemble(source).unwrap()[..count].iter().map(|word| word.as_u16()).collect()
+    }
+
+    #[test]
+    pub fn test_encode_each_form() {
+        let source = "
+            NOP
+            SLEEP
+            TRIS GPIO
+            MOVWF 0x10
+            CLRW
+            CLRF 0x10
+            ADDWF 0x10, F
+            IORWF 0x10, W
+            BSF STATUS, 5
+            GOTO 0x030
+            CALL 0x20
+            MOVLW 0x2A
+        ";
+        assert_eq!(words(source, 12), vec![0x000, 0x003, 0x006, 0x030, 0x040, 0x070, 0x1F0, 0x110, 0x5A3, 0xA30, 0x920, 0xC2A]);
+    }
+
+    #[test]
+    pub fn test_labels_equ_and_org() {
+        let source = "
+COUNT   EQU 0x10        ; file register
+        ORG 0x004
+start:  MOVLW 3
+        MOVWF COUNT
+loop    DECFSZ COUNT, F
+        GOTO loop
+        GOTO start
+        END
+        NOP             ; after END, not assembled
+        ";
+        let program = assemble(source).unwrap();
+        assert_eq!(program[0x000], u12::new(0x000));
+        assert_eq!(words(source, 10)[4..], [0xC03, 0x030, 0x2F0, 0xA06, 0xA04, 0x000]);
+    }
+
+    #[test]
+    pub fn test_errors_report_line() {
+        assert_eq!(
+            assemble("NOP\n  FOO 1"),
+            Err(AssemblerError::UnknownMnemonic { at: line(2), mnemonic: "FOO".to_string() })
+        );
+        assert_eq!(
+            assemble("  MOVLW 0x100"),
+            Err(AssemblerError::OutOfRange { at: line(1), value: 0x100, max: 0xFF })
+        );
+        assert_eq!(
+            assemble("  GOTO nowhere"),
+            Err(AssemblerError::UndefinedSymbol { at: line(1), symbol: "nowhere".to_string() })
+        );
+        assert_eq!(
+            assemble("  ADDWF 0x10, F, W"),
+            Err(AssemblerError::WrongOperandCount { at: line(1), expected: 2, found: 3 })
+        );
+    }
+
+    #[test]
+    pub fn test_assembled_words_decode_in_the_emulator() {
+        use pic_emulator_rs::pic::{PICInstruction, PICInstructionMnemonic::*};
+
+        let source = "  MOVLW 1\n  ADDWF 0x10, W\n  BTFSS STATUS, 2\n  CALL 0x20\n  CLRW\n  TRIS GPIO";
+        let decoded: Vec<_> = assemble(source).unwrap()[..6]
+            .iter()
+            .map(|word| PICInstruction::from_u12(*word).decode_mnemonic())
+            .collect();
+        assert_eq!(decoded, vec![MOVLW, ADDWF, BTFSS, CALL, CLR, TRIS]);
+    }
+
+    #[test]
+    pub fn test_missing_destination_is_file() {
+        use pic_emulator_rs::nbitnumber::u5;
+        use pic_emulator_rs::pic::{FileReg, Operands, PICInstruction, PICInstructionMnemonic};
+
+        assert_eq!(words("  ADDWF 0x10\n  ADDWF 0x10, F\n  DECFSZ 0x11", 3), vec![0x1F0, 0x1F0, 0x2F1]);
+        // the same rule as PICInstruction::encode given only a register
+        let encoded = PICInstruction::encode(PICInstructionMnemonic::DECFSZ, Operands::File(FileReg(u5::new(0x11))));
+        assert_eq!(encoded.map(|instruction| instruction.instruction_raw.as_u16()), Some(0x2F1));
+        assert_eq!(
+            assemble("  ADDWF"),
+            Err(AssemblerError::WrongOperandCount { at: line(1), expected: 2, found: 0 })
+        );
+    }
+
+    #[test]
+    pub fn test_parse_number_bases() {
+        assert_eq!(parse_number("0x2A"), Some(0x2A));
+        assert_eq!(parse_number("0X2a"), Some(0x2A));
+        assert_eq!(parse_number("0b101010"), Some(0x2A));
+        assert_eq!(parse_number("0B11"), Some(0x03));
+        assert_eq!(parse_number("42"), Some(0x2A));
+        assert_eq!(parse_number("0b102"), None);
+        assert_eq!(parse_number("0x"), None);
+    }
+
+    #[test]
+    pub fn test_parse_character_literal() {
+        assert_eq!(parse_number("'A'"), Some(0x41));
+        assert_eq!(parse_number("' '"), Some(0x20));
+        assert_eq!(parse_number("'AB'"), None);
+        assert_eq!(parse_number("''"), None);
+    }
+
+    #[test]
+    pub fn test_literals_in_source() {
+        let source = "
+MASK    EQU 0b00001111  ; binary
+        MOVLW ';'       ; a semicolon inside quotes is not a comment
+        ANDLW MASK
+        XORLW ','
+        BSF 0x10, 0b111
+        RETLW 'z'
+        ";
+        assert_eq!(words(source, 5), vec![0xC3B, 0xE0F, 0xF2C, 0x5F0, 0x87A]);
+    }
+
+    #[test]
+    pub fn test_data_words() {
+        let source = "
+        GOTO after
+table   DW 0x1FF, 0x123, 'A'
+        DT 1, 0x2A
+after   MOVLW table
+        ";
+        assert_eq!(words(source, 7), vec![0xA06, 0x1FF, 0x123, 0x041, 0x801, 0x82A, 0xC01]);
+        assert_eq!(assemble("  DW 0x1000"), Err(AssemblerError::OutOfRange { at: line(1), value: 0x1000, max: 0xFFF }));
+    }
+
+    #[test]
+    pub fn test_line_map_follows_the_pc() {
+        use pic_emulator_rs::pic::{Cpu, PipelinedTuringMachine, PIC10F200};
+
+        let source = "
+        MOVLW 2
+        MOVWF 0x10
+loop    DECFSZ 0x10, F
+        GOTO loop
+done    GOTO done
+        ";
+        let mut pic = PIC10F200::new();
+        let output = load_asm(&mut pic, source).unwrap();
+        assert_eq!(output.source_line(0x000), Some(&line(2)));
+        assert_eq!(output.source_line(0x004), Some(&line(6)));
+        assert_eq!(output.source_line(0x005), None);
+
+        // after MOVLW executes on cycle 3 the next word fetched is the one at loop
+        pic.power_on_initialize();
+        pic.run_cycles(3);
+        assert_eq!(output.source_line(pic.pc().as_u16()), Some(&line(4)));
+    }
+
+    #[test]
+    pub fn test_symbol_from_included_file() {
+        use crate::assembler::assemble_with_includes;
+        use std::collections::HashMap;
+
+        let files = HashMap::from([
+            ("registers.inc", "counter EQU 0x10\nstart   EQU 3\n"),
+            ("delay.inc", "delay   DECFSZ counter, F\n        GOTO delay\n        RETLW 0\n"),
+        ]);
+        let include = |file: &str| files.get(file).map(|source| source.to_string());
+        let source = "
+        #include \"registers.inc\"
+        MOVLW start
+        MOVWF counter
+        CALL delay
+        SLEEP
+        INCLUDE delay.inc
+        ";
+        let output = assemble_with_includes(source, include).unwrap();
+        let words: Vec<u16> = output.words[..7].iter().map(|word| word.get()).collect();
+        assert_eq!(words, vec![0xC03, 0x030, 0x904, 0x003, 0x2F0, 0xA04, 0x800]);
+        // line numbers are within the file each line came from
+        assert_eq!(output.source_line(0x005), Some(&in_file("delay.inc", 2)));
+        assert_eq!(output.source_line(0x003), Some(&line(6)));
+
+        assert_eq!(
+            assemble_with_includes("  INCLUDE missing.inc", include).err(),
+            Some(AssemblerError::MissingInclude { at: line(1), file: "missing.inc".to_string() })
+        );
+        let include_self = |_: &str| Some("  INCLUDE self.inc".to_string());
+        assert_eq!(
+            assemble_with_includes("  INCLUDE self.inc", include_self).err(),
+            Some(AssemblerError::RecursiveInclude { at: in_file("self.inc", 1), file: "self.inc".to_string() })
+        );
+
+        // an error inside an included file names that file
+        let include_bad = |_: &str| Some("  NOP\n  FOO 1\n".to_string());
+        let error = assemble_with_includes("  NOP\n  INCLUDE bad.inc", include_bad).err().unwrap();
+        assert_eq!(error, AssemblerError::UnknownMnemonic { at: in_file("bad.inc", 2), mnemonic: "FOO".to_string() });
+        assert_eq!(error.to_string(), "bad.inc line 2: unknown mnemonic FOO");
+    }
+}
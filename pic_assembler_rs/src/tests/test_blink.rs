@@ -0,0 +1,50 @@
+#[cfg(test)]
+mod test {
+    use pic_emulator_rs::data_memory::GPIO_Masks;
+    use pic_emulator_rs::pic::{PipelinedTuringMachine, PIC10F200};
+
+    use crate::assembler::load_asm;
+
+    // Toggle an LED on GP0 forever, with a DECFSZ loop setting the half period
+    // each pass costs 4 cycles to toggle and reload the counter, 3 per DECFSZ/GOTO for
+    // the DELAY - 1 counts that do not reach zero, 2 for the one that skips, and 2 for
+    // the GOTO back: 4 + 3 * 9 + 2 + 2 = 35 cycles between edges with DELAY = 10
+    const BLINK: &str = "
+counter EQU 0x10
+DELAY   EQU 10
+
+        CLRF GPIO           ; LED off before the pin drives
+        MOVLW 0x0E          ; GP0 output, GP1-GP3 inputs
+        TRIS GPIO
+loop    MOVLW 0x01
+        XORWF GPIO, F       ; toggle GP0
+        MOVLW DELAY
+        MOVWF counter
+delay   DECFSZ counter, F
+        GOTO delay
+        GOTO loop
+";
+
+    #[test]
+    pub fn test_blink_toggles_gp0_every_35_cycles() {
+        let mut pic = PIC10F200::new();
+        load_asm(&mut pic, BLINK).expect("blink should assemble");
+        pic.power_on_initialize();
+
+        let mut level = None;
+        let mut edges = Vec::new();
+        for _ in 0..1_000 {
+            pic.tick();
+            let now = pic.pin_level(GPIO_Masks::GP0);
+            if level.is_some() && now != level {
+                edges.push(pic.cycles());
+            }
+            level = now;
+        }
+
+        // GP0 floats until TRIS makes it an output, edges after that are the blinking
+        let periods: Vec<u64> = edges.windows(2).skip(1).map(|pair| pair[1] - pair[0]).collect();
+        assert!(periods.len() > 20);
+        assert!(periods.iter().all(|period| *period == 35), "{:?}", periods);
+    }
+}
@@ -0,0 +1,90 @@
+#[cfg(test)]
+mod test {
+    #[test]
+    pub fn test_movlw_movwf() {
+        pic_test! {
+            asm: "  MOVLW 0x2A
+                    MOVWF 0x10",
+            cycles: 4,
+            expect_w: 0x2A,
+            expect: [0x10 => 0x2A],
+        }
+    }
+
+    #[test]
+    pub fn test_addwf() {
+        pic_test! {
+            asm: "  MOVLW 0x0F
+                    MOVWF 0x10
+                    MOVLW 0x01
+                    ADDWF 0x10, F",
+            cycles: 6,
+            expect_w: 0x01,
+            expect: [0x10 => 0x10, 0x03 => 0x1A],
+        }
+    }
+
+    #[test]
+    pub fn test_subwf() {
+        pic_test! {
+            asm: "  MOVLW 0x05
+                    MOVWF 0x10
+                    MOVLW 0x07
+                    SUBWF 0x10, W",
+            cycles: 6,
+            expect_w: 0xFE,
+            expect: [0x10 => 0x05, 0x03 => 0x18],
+        }
+    }
+
+    #[test]
+    pub fn test_swapf() {
+        pic_test! {
+            asm: "  MOVLW 0x3C
+                    MOVWF 0x10
+                    SWAPF 0x10, F",
+            cycles: 5,
+            expect_w: 0x3C,
+            expect: [0x10 => 0xC3],
+        }
+    }
+
+    #[test]
+    pub fn test_bsf_bcf() {
+        pic_test! {
+            asm: "  BSF 0x10, 7
+                    BSF 0x10, 0
+                    BCF 0x10, 7",
+            cycles: 5,
+            expect_w: 0x00,
+            expect: [0x10 => 0x01],
+        }
+    }
+
+    #[test]
+    pub fn test_decfsz_skips_at_zero() {
+        pic_test! {
+            asm: "  MOVLW 0x01
+                    MOVWF 0x10
+                    DECFSZ 0x10, F
+                    MOVLW 0xFF      ; skipped
+                    IORLW 0x40",
+            cycles: 8,
+            expect_w: 0x41,
+            expect: [0x10 => 0x00],
+        }
+    }
+
+    #[test]
+    pub fn test_call_retlw() {
+        pic_test! {
+            asm: "  CALL table
+                    MOVWF 0x11
+                    SLEEP
+            table:  RETLW 0x99",
+            cycles: 8,
+            expect_w: 0x99,
+            expect: [0x11 => 0x99],
+        }
+    }
+}
@@ -0,0 +1,82 @@
+#[cfg(test)]
+mod test {
+    use crate::tests::assert_round_trip;
+
+    #[test]
+    pub fn test_round_trip_every_form() {
+        assert_round_trip("
+        NOP
+        OPTION
+        SLEEP
+        CLRWDT
+        TRIS GPIO
+        MOVWF 0x10
+        CLRW
+        CLRF 0x11
+        SUBWF 0x12, W
+        DECF 0x13, F
+        IORWF 0x14, W
+        ANDWF 0x15, F
+        XORWF 0x16, W
+        ADDWF PCL, F
+        MOVF FSR, W
+        COMF 0x17, F
+        INCF 0x18, W
+        DECFSZ 0x19, F
+        RRF 0x1A, W
+        RLF 0x1B, F
+        SWAPF STATUS, W
+        INCFSZ 0x1C, F
+        BCF GPIO, 0
+        BSF STATUS, 5
+        BTFSC STATUS, 2
+        BTFSS 0x1F, 7
+        RETLW 0xFF
+        CALL 0xAA
+        GOTO 0x1FF
+        MOVLW 0x00
+        IORLW 0x81
+        ANDLW 0x0F
+        XORLW 0xF0
+        ");
+    }
+
+    #[test]
+    pub fn test_round_trip_sample_programs() {
+        // blink
+        assert_round_trip("
+counter EQU 0x10
+        CLRF GPIO
+        MOVLW 0x0E
+        TRIS GPIO
+loop    MOVLW 0x01
+        XORWF GPIO, F
+        MOVLW 10
+        MOVWF counter
+delay   DECFSZ counter, F
+        GOTO delay
+        GOTO loop
+        ");
+
+        // a lookup table reached with a computed jump, and a word only DW can place
+        assert_round_trip("
+        MOVLW 2
+        CALL table
+        MOVWF 0x10
+        SLEEP
+table   ADDWF PCL, F
+        DT 'a', 'b', 'c'
+        ORG 0x0F0
+        DW 0x001
+        ");
+
+        // code in the upper page of a 512 word part
+        assert_round_trip("
+        GOTO high
+        ORG 0x100
+high    BSF STATUS, 5
+        CALL 0x20
+        GOTO high
+        ");
+    }
+}
@@ -0,0 +1,61 @@
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+
+use pic_emulator_rs::nbitnumber::u12;
+use pic_emulator_rs::pic::{PICInstruction, PIC10F200, PipelinedTuringMachine, Programmable};
+
+// a mix of ALU, bit, skip and branch instructions that never halts
+fn representative_program() -> [u12; 0x200] {
+    let listing: [u16; 10] = [
+        0xC0A, // MOVLW 10
+        0x030, // MOVWF 0x10
+        0x2B1, // loop: INCF 0x11, F
+        0x1D1, // ADDWF 0x11, W
+        0x391, // SWAPF 0x11, W
+        0x512, // BSF 0x12, 0
+        0x612, // BTFSC 0x12, 0
+        0x412, // BCF 0x12, 0
+        0x2F0, // DECFSZ 0x10, F
+        0xA02, // GOTO loop
+    ];
+    let mut program = [u12::new(0); 0x200];
+    for (address, word) in listing.iter().enumerate() {
+        program[address] = u12::new(*word);
+    }
+    program[0x00A] = u12::new(0xA00); // GOTO 0x000
+    program
+}
+
+const CYCLES: u64 = 10_000;
+
+fn tick(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tick");
+    group.throughput(Throughput::Elements(CYCLES));
+    group.bench_function("representative program", |b| {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(representative_program());
+        pic.power_on_initialize();
+        b.iter(|| {
+            for _ in 0..CYCLES {
+                pic.tick();
+            }
+        })
+    });
+    group.finish();
+}
+
+fn decode(c: &mut Criterion) {
+    let words: Vec<u12> = (0..0x1000).map(u12::new).collect();
+    let mut group = c.benchmark_group("decode");
+    group.throughput(Throughput::Elements(words.len() as u64));
+    group.bench_function("every word", |b| {
+        b.iter(|| {
+            for word in &words {
+                black_box(PICInstruction::from_u12(black_box(*word)).decode_mnemonic());
+            }
+        })
+    });
+    group.finish();
+}
+
+criterion_group!(benches, tick, decode);
+criterion_main!(benches);
@@ -0,0 +1,21 @@
+#![no_main]
+
+use libfuzzer_sys::fuzz_target;
+use pic_emulator_rs::config::DeviceConfig;
+use pic_emulator_rs::loader::{load_hex, validate_hex, HexIssue};
+
+// HEX files come from outside, whatever the bytes are the loader has to return an error
+// rather than panic, and validate_hex has to agree that a file load_hex rejects is malformed
+fuzz_target!(|data: &[u8]| {
+    let Ok(text) = core::str::from_utf8(data) else {
+        return;
+    };
+    let loaded = load_hex(text);
+    let issues = validate_hex(text, &DeviceConfig::default());
+    if loaded.is_ok() {
+        // load_hex fills all 512 words, so on the 256 word default only range issues are left
+        if let Err(issues) = &issues {
+            assert!(issues.iter().all(|issue| matches!(issue, HexIssue::OutOfRange { .. })));
+        }
+    }
+});
@@ -0,0 +1,96 @@
+use alloc::vec;
+use alloc::vec::Vec;
+
+use crate::data_memory::SpecialPurposeRegisters;
+use crate::nbitnumber::{u9, NumberOperations};
+use crate::pic::{PICInstruction, PICInstructionMnemonic};
+use crate::program_memory::{ProgramMemory, RESET_VECTOR};
+
+//Which program addresses execution can reach from reset, worked out without running anything
+pub struct Reachability {
+    reachable: Vec<bool>,
+    erased: Vec<bool>,
+    computed_jumps: Vec<u9>,
+}
+
+impl Reachability {
+    pub fn is_reachable(&self, address: u9) -> bool {
+        self.reachable.get(address.as_usize()).copied().unwrap_or(false)
+    }
+
+    //unreachable words which hold something other than an erased NOP
+    pub fn dead_code(&self) -> Vec<u9> {
+        (0..self.reachable.len())
+            .filter(|address| !self.reachable[*address] && !self.erased[*address])
+            .map(|address| u9::new(address as u16))
+            .collect()
+    }
+
+    //instructions writing PCL, their targets depend on W so the analysis only assumes
+    //the RETLW table which usually follows them is reachable
+    pub fn computed_jumps(&self) -> &[u9] {
+        &self.computed_jumps
+    }
+}
+
+//follow sequential flow, GOTO, CALL and its return, and both ways out of the skips from
+//the OSCCAL word and the reset vector, SLEEP and RETLW end a path
+pub fn reachability(program: &ProgramMemory) -> Reachability {
+    use PICInstructionMnemonic::*;
+
+    let words = program.words();
+    let next = |address: u16, offset: u16| (address + offset) % words;
+
+    let mut reachable = vec![false; words as usize];
+    let mut computed_jumps = Vec::new();
+    let mut pending = vec![words - 1, RESET_VECTOR.as_u16()];
+    while let Some(address) = pending.pop() {
+        if reachable[address as usize] {
+            continue;
+        }
+        reachable[address as usize] = true;
+
+        let instruction = PICInstruction::from_u12(program.fetch(u9::new(address)));
+        let mnemonic = instruction.decode_mnemonic();
+        match mnemonic {
+            SLEEP | RETLW | UND | MOVLB | RETURN | RETFIE => {}
+            GOTO => pending.push(instruction.extract_k_goto().as_u16() % words),
+            CALL => {
+                pending.push(instruction.extract_k() as u16 % words);
+                pending.push(next(address, 1));
+            }
+            DECFSZ | INCFSZ | BTFSC | BTFSS => {
+                pending.push(next(address, 1));
+                pending.push(next(address, 2));
+            }
+            _ if writes_pcl(&instruction, mnemonic) => {
+                computed_jumps.push(u9::new(address));
+                let mut entry = next(address, 1);
+                pending.push(entry);
+                while entry != address && PICInstruction::from_u12(program.fetch(u9::new(entry))).decode_mnemonic() == RETLW {
+                    pending.push(entry);
+                    entry = next(entry, 1);
+                }
+            }
+            _ => pending.push(next(address, 1)),
+        }
+    }
+
+    let erased = (0..words).map(|address| program.fetch(u9::new(address)).as_u16() == 0).collect();
+    computed_jumps.sort_by_key(|address| address.as_u16());
+    Reachability { reachable, erased, computed_jumps }
+}
+
+fn writes_pcl(instruction: &PICInstruction, mnemonic: PICInstructionMnemonic) -> bool {
+    use PICInstructionMnemonic::*;
+
+    let fields = instruction.decoded();
+    let to_pcl = fields.f.is_some_and(|f| f.0 == SpecialPurposeRegisters::PCL.address());
+    match mnemonic {
+        MOVWF | BCF | BSF => to_pcl,
+        CLR | SUBWF | DECF | IORWF | ANDWF | XORWF | ADDWF | MOVF | COMF | INCF | RRF | RLF | SWAPF => {
+            to_pcl && fields.d.is_some_and(|d| d.as_u16() == 1)
+        }
+        _ => false,
+    }
+}
@@ -7,11 +7,13 @@ pub mod SystemClock{
         pub quadrature_clocks: Vec<bool>,
         frequency: u32,
         tick_callback: Box<dyn FnMut() + 'a>,
+        // called with the index of the active phase, 0 is Q1
+        phase_callback: Option<Box<dyn FnMut(u32) + 'a>>,
     }
-    
+
     impl<'a> SystemClock<'a> {
-        pub fn new<F>(phases: u32, frequency: u32, tick_callback: F) -> Self 
-        where 
+        pub fn new<F>(phases: u32, frequency: u32, tick_callback: F) -> Self
+        where
             F: FnMut() + 'a,
         {
             Self {
@@ -20,23 +22,39 @@ pub mod SystemClock{
                 quadrature_clocks: vec![false; phases as usize],
                 frequency,
                 tick_callback: Box::new(tick_callback),
+                phase_callback: None,
             }
         }
-    
-        fn tick(&mut self) {
-            self.phase += 1;
+
+        pub fn set_phase_callback<F>(&mut self, phase_callback: F)
+        where
+            F: FnMut(u32) + 'a,
+        {
+            self.phase_callback = Some(Box::new(phase_callback));
+        }
+
+        pub fn tick(&mut self) {
+            let active_phase = self.phase % self.phases;
             for clk in &mut self.quadrature_clocks {
                 *clk = false;
             }
-            self.quadrature_clocks[(self.phase % self.phases) as usize] = true;
+            self.quadrature_clocks[active_phase as usize] = true;
+            if let Some(phase_callback) = &mut self.phase_callback {
+                phase_callback(active_phase);
+            }
             (self.tick_callback)();
+            self.phase += 1;
         }
-    
-        pub async fn start(&mut self) {
+
+        // runs forever, or until max_cycles instruction cycles (each of `phases` ticks) have elapsed
+        pub async fn start(&mut self, max_cycles: Option<u64>) {
             let tick_duration = Duration::from_secs_f64(1.0 / self.frequency as f64);
+            let max_ticks = max_cycles.map(|cycles| cycles * self.phases as u64);
+            let mut ticks = 0;
             // Start the clock
-            loop {
+            while max_ticks.is_none_or(|max_ticks| ticks < max_ticks) {
                 self.tick();
+                ticks += 1;
                 sleep(tick_duration).await;
             }
         }
@@ -0,0 +1,102 @@
+use crate::pic::PIC10F2Types;
+
+//Per device options, the PIC10F200/202/204/206 share a core and differ in memory and peripherals
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DeviceConfig {
+    pub device: PIC10F2Types,
+    //words of program memory, including the OSCCAL word at the top
+    pub program_words: u16,
+    pub has_comparator: bool,
+    //instruction cycles the device is held in reset after power on, the datasheet's
+    //device reset timer is nominally 18mS (18000 cycles), 0 starts executing straight away
+    pub power_up_delay: u64,
+    //the WDTE configuration bit, left off so programs do not have to CLRWDT
+    pub watchdog_enabled: bool,
+    //instruction cycles per WDT time-out before the prescaler, nominally 18mS
+    pub watchdog_period: u64,
+    //what the registers the datasheet leaves undefined at power on start as
+    pub undefined: UndefinedPolicy,
+}
+
+//Value given to registers whose power on value is undefined (xxxx xxxx in the datasheet),
+//so that programs relying on them are caught reproducibly
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum UndefinedPolicy {
+    Zero,
+    Fill(u8),
+    //a fixed pseudo random value for each register, the same for the same seed
+    Random(u64),
+}
+
+impl UndefinedPolicy {
+    //the same for every caller, tests wanting a recognisable pattern ask for Fill
+    pub const DEFAULT: UndefinedPolicy = UndefinedPolicy::Zero;
+
+    //the power on value of the register at address
+    pub fn value(self, address: u8) -> u8 {
+        match self {
+            UndefinedPolicy::Zero => 0x00,
+            UndefinedPolicy::Fill(value) => value,
+            UndefinedPolicy::Random(seed) => {
+                //splitmix64, enough to make each register differ
+                let mut z = seed.wrapping_add((address as u64 + 1).wrapping_mul(0x9E37_79B9_7F4A_7C15));
+                z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+                z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+                (z ^ (z >> 31)) as u8
+            }
+        }
+    }
+}
+
+impl Default for UndefinedPolicy {
+    fn default() -> Self {
+        UndefinedPolicy::DEFAULT
+    }
+}
+
+impl DeviceConfig {
+    pub const fn new(device: PIC10F2Types) -> Self {
+        let (program_words, has_comparator) = match device {
+            PIC10F2Types::PIC10F200 => (256, false),
+            PIC10F2Types::PIC10F202 => (512, false),
+            PIC10F2Types::PIC10F204 => (256, true),
+            PIC10F2Types::PIC10F206 => (512, true),
+        };
+        DeviceConfig {
+            device,
+            program_words,
+            has_comparator,
+            power_up_delay: 0,
+            watchdog_enabled: false,
+            watchdog_period: 18_000,
+            undefined: UndefinedPolicy::DEFAULT,
+        }
+    }
+
+    //the PC bits the device implements, it wraps from the top of memory back to 0x000
+    pub const fn pc_mask(&self) -> u16 {
+        self.program_words - 1
+    }
+
+    pub const fn with_power_up_delay(mut self, cycles: u64) -> Self {
+        self.power_up_delay = cycles;
+        self
+    }
+
+    pub const fn with_watchdog(mut self, period: u64) -> Self {
+        self.watchdog_enabled = true;
+        self.watchdog_period = period;
+        self
+    }
+
+    pub const fn with_undefined_policy(mut self, policy: UndefinedPolicy) -> Self {
+        self.undefined = policy;
+        self
+    }
+}
+
+impl Default for DeviceConfig {
+    fn default() -> Self {
+        DeviceConfig::new(PIC10F2Types::PIC10F200)
+    }
+}
@@ -1,9 +1,13 @@
+use alloc::vec::Vec;
+
+use crate::config::DeviceConfig;
 use crate::nbitnumber::{NumberOperations, NBitNumber};
-use crate::nbitnumber::{u3, u5, u7, u9, u12};
+use crate::nbitnumber::{u3, u5};
+use crate::pic::PIC10F2Types;
 
 //7 special purpose registers
 //16 general purpose registers
-//0x10 - 0x1F are GP
+//0x10 - 0x1F are GP, the 10F202/206 have 8 more at 0x08 - 0x0F
 //0x00 - 0x0F are special + undefined
 pub const REG_FILE_SIZE : u8 = 0x20;
 pub const REG_FILE_MAX_ADDR : u8 =  0x1F;
@@ -21,26 +25,35 @@ impl Register {
     }
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum SpecialPurposeRegisters {
     INDF = 0x00, //Indirect reference
-    TMR0, //Timer: 8-bit RTC
-    PCL, //Program counter low
-    STATUS , //Status register
-    FSR, // pointer
-    OSCCAL, //oscillator calibration
-    GPIO, //general purpose input/output (pins) 
+    TMR0 = 0x01, //Timer: 8-bit RTC
+    PCL = 0x02, //Program counter low
+    STATUS = 0x03, //Status register
+    FSR = 0x04, // pointer
+    OSCCAL = 0x05, //oscillator calibration
+    GPIO = 0x06, //general purpose input/output (pins) 
     CMCON0 = 0x07, // comparator control
     // 0x008 -> 0x0F is Unimplemented
     // 0x10 -> 0x1F is General Purpose Registers
 }
 
+impl SpecialPurposeRegisters {
+    // file register address of the special register
+    pub fn address(self) -> u5 {
+        u5::new(self as u16)
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum Status_Masks {
     C = 0x00, // carry/borrow flag
     DC = 0x01, // digit carry/borrow flag
     Z = 0x02, // zero flag
     PD = 0x03, // power down flag
     TO = 0x04, // timeout flag
-    // 0x05 is Unimplemented
+    PA0 = 0x05, // reserved on the PIC10F20x, which has no program paging
     CWUF = 0x06, // comparator wake up flag
     GPWUF = 0x07, // general purpose wake up flag
 }
@@ -56,6 +69,7 @@ pub enum OSCCAL_Masks {
     CAL6 = 0x07, 
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum GPIO_Masks {
     GP0 = 0x00,
     GP1 = 0x01,
@@ -63,6 +77,7 @@ pub enum GPIO_Masks {
     GP3 = 0x03,
 }
 
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum CMCON0_Masks {
     CWU = 0x00, // comparator wake up flag
     CPREF = 0x01, // comparator positive reference
@@ -74,34 +89,116 @@ pub enum CMCON0_Masks {
     COUT = 0x07, // comparator output
 }
 
-#[derive(Default)]
+// OPTION is not in the register file, it is written with the OPTION instruction
+pub enum OPTION_Masks {
+    PS0 = 0x00, // prescaler rate select
+    PS1 = 0x01,
+    PS2 = 0x02,
+    PSA = 0x03, // prescaler assignment, 1 is WDT and 0 is TMR0
+    T0SE = 0x04, // timer 0 source edge, 1 is falling edge
+    T0CS = 0x05, // timer 0 clock source, 1 is the T0CKI pin
+    GPPU = 0x06, // weak pull-ups disabled when set
+    GPWU = 0x07, // wake up on pin change disabled when set
+}
+
+// unimplemented registers read as 0 and ignore writes, CMCON0 is only on the parts with
+// the comparator and the 10F202/206 have general purpose RAM from 0x08 where the
+// 10F200/204 leave a gap
+fn device_implements(device: PIC10F2Types, address: u5) -> bool {
+    let ram_from_0x08 = matches!(device, PIC10F2Types::PIC10F202 | PIC10F2Types::PIC10F206);
+    match address.as_u16() {
+        0x07 => DeviceConfig::new(device).has_comparator,
+        0x08..=0x0F => ram_from_0x08,
+        _ => true,
+    }
+}
+
+//A register whose value differs between two snapshots of the register file
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct RegisterDiff {
+    pub address: u5,
+    pub old: u8,
+    pub new: u8,
+}
+
+// FSR only has the 5 bits needed to address the file
+pub const FSR_UNIMPLEMENTED_BITS: u8 = 0xE0;
+
+#[derive(Clone, Copy)]
 pub struct RegisterFile {
-    registers: [Register; REG_FILE_SIZE as usize]
+    registers: [Register; REG_FILE_SIZE as usize],
+    // most recent write which was lost to an unimplemented address
+    unimplemented_write: Option<u5>,
+    // the part whose memory map decides which addresses are implemented
+    device: PIC10F2Types,
+}
+
+impl Default for RegisterFile {
+    fn default() -> Self {
+        RegisterFile::new()
+    }
 }
 
 impl RegisterFile {
 
     pub fn new() -> Self {
+        RegisterFile::with_config(&DeviceConfig::default())
+    }
+
+    pub fn with_config(config: &DeviceConfig) -> Self {
         RegisterFile {
-            registers: [Register::new(); REG_FILE_SIZE as usize]
+            registers: [Register::new(); REG_FILE_SIZE as usize],
+            unimplemented_write: None,
+            device: config.device,
+        }
+    }
+
+    pub fn is_implemented(config: &DeviceConfig, address: u5) -> bool {
+        device_implements(config.device, address)
+    }
+
+    fn implements(&self, address: u5) -> bool {
+        device_implements(self.device, address)
+    }
+
+    //every address is_implemented accepts on the device, for viewers that should not show phantom RAM
+    pub fn implemented_addresses(config: &DeviceConfig) -> Vec<u5> {
+        (0..REG_FILE_SIZE as u16).map(u5::new).filter(|address| RegisterFile::is_implemented(config, *address)).collect()
+    }
+
+    pub fn unimplemented_write(&self) -> Option<u5> {
+        self.unimplemented_write
+    }
+
+    //INDF is not a physical register, accesses through it go to the register FSR points at
+    //only the low 5 bits of FSR address the file
+    pub fn resolve(&self, address: u5) -> u5 {
+        if address == SpecialPurposeRegisters::INDF.address() {
+            return u5::new(self.registers[SpecialPurposeRegisters::FSR as usize].value as u16);
         }
+        address
     }
 
-    pub fn write(&mut self, address: u5, val : u8) -> () {
-        
-        if address.as_usize() == SpecialPurposeRegisters::INDF as usize  {
-            // this is not a physical address
+    pub fn write(&mut self, address: u5, val : u8) {
+        let address = self.resolve(address);
+        if address == SpecialPurposeRegisters::INDF.address() {
+            // INDF writing itself is a no-op
             return;
         }
-        if (0x08..=0x0F).contains(&address.as_usize()) {
-            // unimplemented registers in PIC10F200
+        if !self.implements(address) {
+            self.unimplemented_write = Some(address);
             return;
         }
 
+        if address == SpecialPurposeRegisters::FSR.address() {
+            // FSR<7:5> are unimplemented and read as 1
+            self.registers[address.as_usize()].value = val | FSR_UNIMPLEMENTED_BITS;
+            return;
+        }
         self.registers[address.as_usize()].value = val;
     }
 
-    pub fn set_flag(&mut self, mask: u3, val: NBitNumber<1>) -> () {
+    pub fn set_flag(&mut self, mask: u3, val: NBitNumber<1>) {
         //this function is used to set a bit of the status register to a value
         if mask.as_u16() > 0x07 {
             panic!("Invalid mask for status register");
@@ -110,31 +207,79 @@ impl RegisterFile {
         if val == NBitNumber::new(1) {
             self.registers[SpecialPurposeRegisters::STATUS as usize].value |= 1 << mask.as_u16();
         } else {
-            self.registers[SpecialPurposeRegisters::STATUS as usize].value &= 0 << mask.as_u16();
+            self.registers[SpecialPurposeRegisters::STATUS as usize].value &= !(1 << mask.as_u16());
         }
     }
     
+    pub fn get_flag(&self, mask: u3) -> NBitNumber<1> {
+        //this function is used to read a bit of the status register
+        if mask.as_u16() > 0x07 {
+            panic!("Invalid mask for status register");
+        }
+
+        let status = self.registers[SpecialPurposeRegisters::STATUS as usize].value;
+        NBitNumber::new(((status >> mask.as_u16()) & 0x01) as u16)
+    }
+
+    //named STATUS bits, these write the register directly so TO and PD can be set too
+    pub fn status(&self, bit: Status_Masks) -> bool {
+        self.special_bit(SpecialPurposeRegisters::STATUS, bit as u8)
+    }
+
+    pub fn set_status(&mut self, bit: Status_Masks, value: bool) {
+        self.set_special_bit(SpecialPurposeRegisters::STATUS, bit as u8, value);
+    }
+
+    //named CMCON0 bits, only meaningful on the parts with the comparator
+    pub fn cmcon0(&self, bit: CMCON0_Masks) -> bool {
+        self.special_bit(SpecialPurposeRegisters::CMCON0, bit as u8)
+    }
+
+    pub fn set_cmcon0(&mut self, bit: CMCON0_Masks, value: bool) {
+        self.set_special_bit(SpecialPurposeRegisters::CMCON0, bit as u8, value);
+    }
+
+    fn special_bit(&self, register: SpecialPurposeRegisters, bit: u8) -> bool {
+        (self.registers[register as usize].value >> bit) & 0x01 == 1
+    }
+
+    fn set_special_bit(&mut self, register: SpecialPurposeRegisters, bit: u8, value: bool) {
+        let register = &mut self.registers[register as usize].value;
+        if value {
+            *register |= 1 << bit;
+        } else {
+            *register &= !(1 << bit);
+        }
+    }
+
+    //clear RAM and the special purpose registers back to 0, the power on values
+    //(OSCCAL, STATUS, FSR, ...) are set by PipelinedTuringMachine::power_on_initialize
     pub fn flash(&mut self) {
         // set all memory locations to 0
-        self.registers = [Register::new(); REG_FILE_SIZE as usize]
+        self.registers = [Register::new(); REG_FILE_SIZE as usize];
+        self.unimplemented_write = None;
     }
 
     pub fn read(&self, address: u5) -> u8 {
-        // indirect referencing
-        if address == u5::new(SpecialPurposeRegisters::INDF as u16) {
-            // grab the the reference int he fsr register
-            let fsr_pointer = self.registers[SpecialPurposeRegisters::FSR as usize].value;
-            // grab the value at that address that was in the fsr register
-            let value = self.registers[fsr_pointer as usize].value;
-            return value;
-        }
-
-        
-        if (0x08..=0x0F).contains(&address.as_u16()) {
-            // unimplemented registers in PIC10F200
+        let address = self.resolve(address);
+        if address == SpecialPurposeRegisters::INDF.address() || !self.implements(address) {
+            // INDF addressing itself or an unimplemented register reads as 0
             return 0x00;
         }
 
         self.registers[address.as_usize()].value
     }
-}
\ No newline at end of file
+
+    pub fn diff(&self, other: &RegisterFile) -> Vec<RegisterDiff> {
+        // compares the stored values, so INDF is never reported
+        let mut differences = Vec::new();
+        for address in 0..REG_FILE_SIZE {
+            let old = self.registers[address as usize].value;
+            let new = other.registers[address as usize].value;
+            if old != new {
+                differences.push(RegisterDiff { address: u5::new(address as u16), old, new });
+            }
+        }
+        differences
+    }
+}
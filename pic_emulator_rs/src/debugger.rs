@@ -0,0 +1,297 @@
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::data_memory::{GPIO_Masks, RegisterFile};
+use crate::nbitnumber::{u5, u9, NBitNumber, NumberOperations};
+use crate::pic::{HaltReason, PICInstruction, PIC10F200, PipelinedTuringMachine};
+
+//Everything an instruction cycle can change, kept for step_back, the flash is left
+//out as no instruction writes it, only the return stack beside it
+pub(crate) struct Snapshot {
+    data_memory: RegisterFile,
+    stack: [u9; 2],
+    program_counter: u9,
+    instruction_register: PICInstruction,
+    instruction_address: Option<u9>,
+    pipeline_flush: bool,
+    w_register: u8,
+    w_written: bool,
+    option_register: u8,
+    tris_register: u8,
+    io_pins: [Option<bool>; 4],
+    stimulus: Vec<(u64, GPIO_Masks, bool)>,
+    comparator_output: bool,
+    prescaler: u16,
+    watchdog: u64,
+    cycles: u64,
+    reset_cycles: u64,
+    halt_reason: Option<HaltReason>,
+    stack_depth: u8,
+    stack_overflows: u32,
+}
+
+//Breakpoints stop before the instruction at an address executes,
+//watchpoints stop after an instruction cycle changes a register
+impl PIC10F200 {
+    pub fn add_breakpoint(&mut self, address: u9) {
+        self.breakpoints[address.as_usize()] = true;
+    }
+
+    pub fn remove_breakpoint(&mut self, address: u9) {
+        self.breakpoints[address.as_usize()] = false;
+    }
+
+    pub fn breakpoints(&self) -> Vec<u9> {
+        set_addresses(&self.breakpoints)
+    }
+
+    pub fn clear_all_breakpoints(&mut self) {
+        self.breakpoints = [false; 0x200];
+    }
+
+    pub fn add_watchpoint(&mut self, address: u5) {
+        self.watchpoints[address.as_usize()] = true;
+    }
+
+    pub fn remove_watchpoint(&mut self, address: u5) {
+        self.watchpoints[address.as_usize()] = false;
+    }
+
+    pub fn watchpoints(&self) -> Vec<u5> {
+        set_addresses(&self.watchpoints)
+    }
+
+    pub fn clear_all_watchpoints(&mut self) {
+        self.watchpoints = [false; 0x20];
+    }
+
+    //start executing from address instead of the reset vector, to run a subroutine on its own
+    //the pipeline is refilled from address, so the next instruction cycle executes it
+    pub fn set_pc(&mut self, address: u9) -> Result<(), AddressOutOfRange> {
+        if address.as_u16() >= self.config.program_words {
+            return Err(AddressOutOfRange(address));
+        }
+        self.program_counter = address;
+        self.pipeline_flush = false;
+        self.fetch();
+        Ok(())
+    }
+
+    //keep the state ahead of the last depth instruction cycles so step_back can undo them,
+    //0 turns the history off and drops it
+    pub fn set_history_depth(&mut self, depth: usize) {
+        self.history_depth = depth;
+        while self.history.len() > depth {
+            self.history.pop_front();
+        }
+    }
+
+    //undo the last instruction cycle, false once the history is used up
+    pub fn step_back(&mut self) -> bool {
+        let Some(snapshot) = self.history.pop_back() else {
+            return false;
+        };
+        self.data_memory = snapshot.data_memory;
+        self.program_memory.stack_mut().copy_from_slice(&snapshot.stack);
+        self.program_counter = snapshot.program_counter;
+        self.instruction_register = snapshot.instruction_register;
+        self.instruction_address = snapshot.instruction_address;
+        self.pipeline_flush = snapshot.pipeline_flush;
+        self.w_register = snapshot.w_register;
+        self.w_written = snapshot.w_written;
+        self.option_register = snapshot.option_register;
+        self.tris_register = snapshot.tris_register;
+        self.io_pins = snapshot.io_pins;
+        self.stimulus = snapshot.stimulus;
+        self.comparator_output = snapshot.comparator_output;
+        self.prescaler = snapshot.prescaler;
+        self.watchdog = snapshot.watchdog;
+        self.cycles = snapshot.cycles;
+        self.reset_cycles = snapshot.reset_cycles;
+        self.halt_reason = snapshot.halt_reason;
+        self.stack_depth = snapshot.stack_depth;
+        self.stack_overflows = snapshot.stack_overflows;
+        true
+    }
+
+    pub(crate) fn record_history(&mut self) {
+        if self.history.len() == self.history_depth {
+            self.history.pop_front();
+        }
+        self.history.push_back(Snapshot {
+            data_memory: self.data_memory,
+            stack: [self.program_memory.stack()[0], self.program_memory.stack()[1]],
+            program_counter: self.program_counter,
+            instruction_register: self.instruction_register,
+            instruction_address: self.instruction_address,
+            pipeline_flush: self.pipeline_flush,
+            w_register: self.w_register,
+            w_written: self.w_written,
+            option_register: self.option_register,
+            tris_register: self.tris_register,
+            io_pins: self.io_pins,
+            stimulus: self.stimulus.clone(),
+            comparator_output: self.comparator_output,
+            prescaler: self.prescaler,
+            watchdog: self.watchdog,
+            cycles: self.cycles,
+            reset_cycles: self.reset_cycles,
+            halt_reason: self.halt_reason,
+            stack_depth: self.stack_depth,
+            stack_overflows: self.stack_overflows,
+        });
+    }
+
+    //return addresses pushed and not yet popped, the one RETLW pops next comes first
+    pub fn call_stack(&self) -> &[u9] {
+        &self.program_memory.stack()[..self.stack_depth as usize]
+    }
+
+    //overwrite a return address, level 0 is the top of the stack, only levels in call_stack can be set
+    pub fn set_stack_entry(&mut self, level: usize, address: u9) -> Result<(), StackLevelOutOfRange> {
+        if level >= self.stack_depth as usize {
+            return Err(StackLevelOutOfRange(level));
+        }
+        self.program_memory.stack_mut()[level] = address;
+        Ok(())
+    }
+
+    //halt once more than limit CALLs in a row have pushed a return address off the
+    //two level stack without a RETLW in between, None turns the trap off
+    pub fn set_recursion_trap(&mut self, limit: Option<u32>) {
+        self.recursion_limit = limit;
+    }
+
+    pub(crate) fn stack_pushed(&mut self) {
+        if self.stack_depth < 2 {
+            self.stack_depth += 1;
+            return;
+        }
+        self.stack_overflows += 1;
+        if self.recursion_limit.is_some_and(|limit| self.stack_overflows > limit) {
+            self.halt(HaltReason::RunawayRecursion);
+        }
+    }
+
+    pub(crate) fn stack_popped(&mut self) {
+        self.stack_depth = self.stack_depth.saturating_sub(1);
+        self.stack_overflows = 0;
+    }
+
+    //continue after stopping at a breakpoint or watchpoint, other halts are left alone
+    pub fn resume(&mut self) {
+        if matches!(self.halt_reason, Some(HaltReason::Breakpoint(_)) | Some(HaltReason::Watchpoint(_))) {
+            self.halt_reason = None;
+        }
+    }
+
+    //the word at the PC decoded, the instruction fetched next, whereas the instruction
+    //register holds the one fetched last cycle which is about to execute
+    pub fn current_instruction(&self) -> PICInstruction {
+        PICInstruction::from_u12(self.program_memory.fetch(self.program_counter))
+    }
+
+    //where execution goes after the instruction in the execute stage, accounting for
+    //GOTO, CALL, RETLW, skips and writes to PCL, worked out on a scratch copy of the core
+    pub fn next_pc(&self) -> u9 {
+        let mut scratch = self.scratch();
+        scratch.execute();
+        scratch.program_counter
+    }
+
+    //instruction cycles until the instruction at address is next to execute, where a
+    //breakpoint on it would stop, run on a scratch copy so the core is left as it is
+    //None when the program halts or limit cycles pass first
+    pub fn estimate_cycles_to(&self, address: u9, limit: u64) -> Option<u64> {
+        let mut scratch = self.scratch();
+        scratch.reset_cycles = self.reset_cycles;
+        scratch.halt_reason = self.halt_reason;
+        for cycles in 0..=limit {
+            //the word fetched alongside a SLEEP never executes
+            if scratch.halt_reason.is_some() {
+                return None;
+            }
+            if scratch.instruction_address == Some(address) {
+                return Some(cycles);
+            }
+            scratch.tick();
+        }
+        None
+    }
+
+    //the state instructions can see, without the hooks, debug points or stimulus
+    fn scratch(&self) -> PIC10F200 {
+        let mut scratch = PIC10F200::with_config(self.config);
+        scratch.data_memory = self.data_memory;
+        scratch.program_memory = self.program_memory;
+        scratch.program_counter = self.program_counter;
+        scratch.instruction_register = self.instruction_register;
+        scratch.instruction_address = self.instruction_address;
+        scratch.w_register = self.w_register;
+        scratch.option_register = self.option_register;
+        scratch.tris_register = self.tris_register;
+        scratch.io_pins = self.io_pins;
+        scratch.comparator_inputs = self.comparator_inputs;
+        scratch.comparator_output = self.comparator_output;
+        scratch.prescaler = self.prescaler;
+        scratch.watchdog = self.watchdog;
+        scratch.executors = self.executors.clone();
+        scratch
+    }
+
+    //called at the end of every instruction cycle, before is the register file ahead of
+    //the cycle and is only taken while a watchpoint is set
+    pub(crate) fn check_debug_points(&mut self, before: Option<RegisterFile>) {
+        if self.halt_reason.is_some() {
+            return;
+        }
+
+        if let Some(before) = before {
+            let changed = before.diff(&self.data_memory)
+                .into_iter()
+                .find(|diff| self.watchpoints[diff.address.as_usize()]);
+            if let Some(diff) = changed {
+                self.halt(HaltReason::Watchpoint(diff.address));
+                return;
+            }
+        }
+
+        if let Some(address) = self.instruction_address {
+            if self.breakpoints[address.as_usize()] {
+                self.halt(HaltReason::Breakpoint(address));
+            }
+        }
+    }
+}
+
+fn set_addresses<const N: usize>(set: &[bool]) -> Vec<NBitNumber<N>> {
+    set.iter()
+        .enumerate()
+        .filter(|(_, is_set)| **is_set)
+        .map(|(address, _)| NBitNumber::new(address as u16))
+        .collect()
+}
+
+//A program address past the memory the device implements
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct AddressOutOfRange(pub u9);
+
+impl fmt::Display for AddressOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "address 0x{:03X} is outside program memory", self.0.as_u16())
+    }
+}
+
+impl core::error::Error for AddressOutOfRange {}
+
+//A stack level holding no return address
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct StackLevelOutOfRange(pub usize);
+
+impl fmt::Display for StackLevelOutOfRange {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "stack level {} holds no return address", self.0)
+    }
+}
+
+impl core::error::Error for StackLevelOutOfRange {}
@@ -0,0 +1,71 @@
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::string::{String, ToString};
+
+use crate::nbitnumber::{u12, u9, NumberOperations};
+use crate::pic::{Cpu, PICInstruction, PICInstructionMnemonic};
+
+//Names of the special purpose registers, indexed by address
+pub const SPECIAL_REGISTER_NAMES: [&str; 8] = ["INDF", "TMR0", "PCL", "STATUS", "FSR", "OSCCAL", "GPIO", "CMCON0"];
+
+//Register names used when disassembling, user names (e.g. from EQU) take priority
+#[derive(Default)]
+pub struct SymbolTable {
+    registers: BTreeMap<u16, String>,
+}
+
+impl SymbolTable {
+    pub fn new() -> Self {
+        SymbolTable { registers: BTreeMap::new() }
+    }
+
+    pub fn insert(&mut self, name: &str, address: u16) {
+        self.registers.insert(address, name.to_string());
+    }
+
+    pub fn register_name(&self, address: u16) -> Option<&str> {
+        if let Some(name) = self.registers.get(&address) {
+            return Some(name);
+        }
+        SPECIAL_REGISTER_NAMES.get(address as usize).copied()
+    }
+}
+
+fn register(address: u16, symbols: Option<&SymbolTable>) -> String {
+    match symbols.and_then(|symbols| symbols.register_name(address)) {
+        Some(name) => name.to_string(),
+        None => format!("0x{:02X}", address),
+    }
+}
+
+//Disassemble a single program word, register operands are named when a symbol table is given
+pub fn disassemble(word: u12, symbols: Option<&SymbolTable>) -> String {
+    use PICInstructionMnemonic::*;
+
+    let instruction = PICInstruction::from_u12(word);
+    let mnemonic = instruction.decode_mnemonic();
+    let name = mnemonic.name();
+    let f = register(instruction.extract_f().as_u16(), symbols);
+    let d = if instruction.extract_d().as_u16() == 0 { "W" } else { "F" };
+
+    match mnemonic {
+        NOP | OPTION | SLEEP | CLRWDT => name.to_string(),
+        TRIS => format!("{} {}", name, register(word.as_u16() & 0x007, symbols)),
+        MOVWF => format!("{} {}", name, f),
+        // CLR is written as CLRW or CLRF depending on the destination
+        CLR if d == "W" => "CLRW".to_string(),
+        CLR => format!("CLRF {}", f),
+        SUBWF | DECF | IORWF | ANDWF | XORWF | ADDWF | MOVF | COMF | INCF | DECFSZ | RRF | RLF | SWAPF | INCFSZ => {
+            format!("{} {}, {}", name, f, d)
+        }
+        BCF | BSF | BTFSC | BTFSS => format!("{} {}, {}", name, f, instruction.extract_b().as_u16()),
+        GOTO => format!("{} 0x{:03X}", name, instruction.extract_k_goto().as_u16()),
+        CALL | RETLW | MOVLW | IORLW | ANDLW | XORLW => format!("{} 0x{:02X}", name, instruction.extract_k()),
+        MOVLB | RETURN | RETFIE | UND => format!("UND 0x{:03X}", word.as_u16()),
+    }
+}
+
+//Disassemble the word a core holds at address
+pub fn disassemble_at(cpu: &dyn Cpu, address: u9, symbols: Option<&SymbolTable>) -> String {
+    disassemble(cpu.program_word(address), symbols)
+}
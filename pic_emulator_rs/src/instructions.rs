@@ -1,43 +1,87 @@
-use crate::nbitnumber::{u12, u9, NumberOperations, NBitNumber};
-use crate::data_memory::Status_Masks;
-use crate::pic::PIC10F200;
+use crate::nbitnumber::{u5, u9, NumberOperations, NBitNumber};
+use crate::data_memory::{SpecialPurposeRegisters, Status_Masks};
+use crate::opcodes::{FLAG_PD, FLAG_TO, OPCODES};
+use crate::peripherals::clear_watchdog;
+use crate::pic::{Executor, HaltReason, PICInstructionMnemonic, PIC10F200};
 
 // All instruction are single cycle except for branches (which are two cycle)
 
 pub fn HALT(pic: &mut PIC10F200)  {
-    todo!()
-    //halt the program counter
+    //halt the program counter, only reached by undefined instructions
+    pic.halt(HaltReason::UndefinedInstruction);
+}
+
+//The built in semantics of a mnemonic, the enhanced core instructions halt like UND
+pub fn executor(mnemonic: PICInstructionMnemonic) -> Executor {
+    use PICInstructionMnemonic as M;
+    match mnemonic {
+        M::NOP => NOP,
+        M::OPTION => OPTION,
+        M::SLEEP => SLEEP,
+        M::CLRWDT => CLRWDT,
+        M::TRIS => TRIS,
+        M::MOVWF => MOVWF,
+        M::CLR => CLR,
+        M::SUBWF => SUBWF,
+        M::DECF => DECF,
+        M::IORWF => IORWF,
+        M::ANDWF => ANDWF,
+        M::XORWF => XORWF,
+        M::ADDWF => ADDWF,
+        M::MOVF => MOVF,
+        M::COMF => COMF,
+        M::INCF => INCF,
+        M::DECFSZ => DECFSZ,
+        M::RRF => RRF,
+        M::RLF => RLF,
+        M::SWAPF => SWAPF,
+        M::INCFSZ => INCFSZ,
+        M::BCF => BCF,
+        M::BSF => BSF,
+        M::BTFSC => BTFSC,
+        M::BTFSS => BTFSS,
+        M::RETLW => RETLW,
+        M::CALL => CALL,
+        M::GOTO => GOTO,
+        M::MOVLW => MOVLW,
+        M::IORLW => IORLW,
+        M::ANDLW => ANDLW,
+        M::XORLW => XORLW,
+        M::MOVLB | M::RETURN | M::RETFIE | M::UND => HALT,
+    }
 }
 
 /* Miscellaneous */
 
-pub fn NOP(pic: &mut PIC10F200)  {
+pub fn NOP(_pic: &mut PIC10F200)  {
     //Do nothing
-    return;
 }
 
 pub fn OPTION(pic: &mut PIC10F200)  {
     //Copy the contents of W to OPTION register
-    //Option may or may not be implemented in the emulator
-    todo!()
+    pic.option_register = pic.w_register;
 }
 
 pub fn SLEEP(pic: &mut PIC10F200)  {
-    todo!();
-    pic.data_memory.set_flag(NBitNumber::new(Status_Masks::TO as u16) , NBitNumber::new(1));
-    pic.data_memory.set_flag(NBitNumber::new(Status_Masks::PD as u16) , NBitNumber::new(0));
-    //also set the prescaler to 0 in option register
+    pic.data_memory.set_status(Status_Masks::TO, true);
+    pic.data_memory.set_status(Status_Masks::PD, false);
+    clear_watchdog(pic);
+    pic.halt(HaltReason::Sleep);
 }
 
 pub fn CLRWDT(pic: &mut PIC10F200)  {
-    todo!();
-    pic.data_memory.set_flag(NBitNumber::new(Status_Masks::TO as u16) , NBitNumber::new(1));
-    pic.data_memory.set_flag(NBitNumber::new(Status_Masks::PD as u16) , NBitNumber::new(1));
-    //also set the prescaler to 0 in option register
+    pic.data_memory.set_status(Status_Masks::TO, true);
+    pic.data_memory.set_status(Status_Masks::PD, true);
+    clear_watchdog(pic);
 }
 
 pub fn TRIS(pic: &mut PIC10F200)  {
-    todo!()
+    //Copy the contents of W to the GPIO tri-state register, only 4 pins exist
+    //the low 2 bits pick the port, GPIO (6) is the only port, so TRIS 5 and TRIS 7 do nothing
+    if pic.instruction_register.extract_f_tris().as_u16() != SpecialPurposeRegisters::GPIO as u16 & 0x003 {
+        return;
+    }
+    pic.tris_register = pic.w_register & 0x0F;
 }
 
 /* ALU Operation*/
@@ -45,29 +89,103 @@ pub fn TRIS(pic: &mut PIC10F200)  {
 fn store_wf(pic: &mut PIC10F200, result: u8){
     //if d is '0' store result in W, if d is '1' store result in f
     let d = pic.instruction_register.extract_d();
-    if d.as_u16() == 1{
+    if d.as_u16() == 0 {
         pic.w_register = result;
     } else {
-        pic.data_memory.write(pic.instruction_register.extract_f(), result);
+        write_f(pic, pic.instruction_register.extract_f(), result);
+    }
+}
+
+//What wrote the PC, each forms the 9 bit PC from its value differently
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub(crate) enum PcSource {
+    Goto,
+    Call,
+    PclWrite,
+    Return,
+}
+
+//the one place the PC bits above the value are decided:
+//GOTO has all 9 bits, CALL only 8 so PC<8> is cleared, and a PCL write clears PC<8>
+//so computed jumps stay in the first 256 words, a return restores the address pushed
+//the 10F20x has no program paging, GOTO's 9 bits already cover all 512 words and STATUS<5>
+//is reserved, the result is masked to the words the device implements
+pub(crate) fn form_pc(pic: &PIC10F200, source: PcSource, value: u16) -> u9 {
+    let pc = match source {
+        PcSource::Goto => value & 0x1FF,
+        PcSource::Call => value & 0x0FF,
+        PcSource::PclWrite => value & 0x0FF,
+        PcSource::Return => value & 0x1FF,
+    };
+    u9::new(pc & pic.config.pc_mask())
+}
+
+//branches, skips and writes to PCL discard the instruction already fetched, even when
+//the new PC happens to equal the old one (e.g. GOTO $+1), so they always cost a cycle
+fn jump(pic: &mut PIC10F200, target: u9) {
+    pic.program_counter = target;
+    pic.pipeline_flush = true;
+}
+
+//a skip steps over the instruction already fetched, wrapping at the top of program memory
+fn skip(pic: &mut PIC10F200) {
+    let next = (pic.program_counter.as_u16() + 1) & pic.config.pc_mask();
+    jump(pic, u9::new(next));
+}
+
+//every instruction that writes a file register goes through here so the special targets are handled once
+fn write_f(pic: &mut PIC10F200, f: u5, value: u8) {
+    let f = pic.data_memory.resolve(f);
+    if f == SpecialPurposeRegisters::PCL.address() {
+        //writing PCL is a computed jump, PC<8> is cleared so ADDWF PCL, F stays in the
+        //first 256 words and the carry out of PCL only reaches STATUS C, never the PC
+        pic.data_memory.write(f, value);
+        jump(pic, form_pc(pic, PcSource::PclWrite, value as u16));
+    } else if f == SpecialPurposeRegisters::STATUS.address() {
+        //TO and PD are read only, and flags the instruction sets from its result win over the write
+        let mnemonic = pic.instruction_register.decode_mnemonic();
+        let affected = OPCODES.iter().find(|entry| entry.mnemonic == mnemonic).map_or(0, |entry| entry.status);
+        let preserved = FLAG_TO | FLAG_PD | affected;
+        let current = pic.data_memory.read(f);
+        pic.data_memory.write(f, (value & !preserved) | (current & preserved));
+    } else {
+        pic.data_memory.write(f, value);
     }
 }
 
 fn update_Z(pic: &mut PIC10F200, result: u8){ 
-    let is_zero:NBitNumber<1> = NBitNumber::new((result == 0) as u16);
-    pic.data_memory.set_flag(NBitNumber::new(Status_Masks::Z as u16) , is_zero);
+    pic.data_memory.set_status(Status_Masks::Z, result == 0);
 }
 
-fn update_C() {
-    todo!()
+fn update_C(pic: &mut PIC10F200, carry: bool){
+    pic.data_memory.set_status(Status_Masks::C, carry);
 }
 
-fn update_DC() {
-    todo!()
+fn update_DC(pic: &mut PIC10F200, digit_carry: bool){
+    //carry out of the low nibble
+    pic.data_memory.set_status(Status_Masks::DC, digit_carry);
 }
 
 fn get_f_value(pic: &mut PIC10F200) -> u8 {
     let f = pic.instruction_register.extract_f();
-    return pic.data_memory.read(f)
+    read_f(pic, f)
+}
+
+//every instruction that reads a file register goes through here, GPIO reads the pins rather than the latch
+//and PCL the PC
+//the read is the Q2 sample, after stimulus for the cycle has been applied at Q1, so output
+//pins give their latch and input pins the level driven at that moment
+fn read_f(pic: &PIC10F200, f: u5) -> u8 {
+    let f = pic.data_memory.resolve(f);
+    if f == SpecialPurposeRegisters::GPIO.address() {
+        return pic.gpio_pins();
+    }
+    if f == SpecialPurposeRegisters::PCL.address() {
+        //PCL is the low byte of the PC itself, which already points past the executing
+        //instruction as its successor was fetched, so MOVF PCL, W at 0x010 reads 0x11
+        return pic.program_counter.as_u16() as u8;
+    }
+    pic.data_memory.read(f)
 }
 
 pub fn MOVWF(pic: &mut PIC10F200)  {
@@ -75,7 +193,7 @@ pub fn MOVWF(pic: &mut PIC10F200)  {
     let f = pic.instruction_register.extract_f();
     let w = pic.w_register;
 
-    pic.data_memory.write(f, w);
+    write_f(pic, f, w);
 }
 
 pub fn CLR(pic: &mut PIC10F200)  {
@@ -88,18 +206,21 @@ pub fn CLR(pic: &mut PIC10F200)  {
 pub fn SUBWF(pic: &mut PIC10F200)  {
     // dest <- f - W
     let f_value = get_f_value(pic);
-    let result = pic.w_register - f_value;
+    let w = pic.w_register;
+    let result = (f_value as u16 + NBitNumber::<8>::new(w as u16).twos_complement().get()) as u8;
 
+    //the ALU adds ~W with a carry in of 1, so C and DC are set when there is no borrow
+    let inverted_w = !w as u16;
     update_Z(pic, result);
-    update_C();
-    update_DC();
+    update_C(pic, f_value as u16 + inverted_w + 1 > 0xFF);
+    update_DC(pic, (f_value as u16 & 0x0F) + (inverted_w & 0x0F) + 1 > 0x0F);
     store_wf(pic, result);
 }
 
 pub fn DECF(pic: &mut PIC10F200)  {
     // dest <- f - 1
     let f_value = get_f_value(pic);
-    let result = f_value - 1;
+    let result = f_value.wrapping_sub(1);
 
     update_Z(pic, result);
     store_wf(pic, result);
@@ -134,16 +255,17 @@ pub fn XORWF(pic: &mut PIC10F200)  {
 pub fn ADDWF(pic: &mut PIC10F200)  {
     // dest <- f+W 
     let f_value = get_f_value(pic);
-    let result = f_value + pic.w_register;
+    let w = pic.w_register;
+    let (result, carry) = f_value.overflowing_add(w);
 
     update_Z(pic, result);
-    update_C();
-    update_DC();
+    update_C(pic, carry);
+    update_DC(pic, (f_value & 0x0F) + (w & 0x0F) > 0x0F);
     store_wf(pic, result);
 }
 
 pub fn MOVF(pic: &mut PIC10F200)  {
-    // dest <- f
+    // dest <- f, with d = 1 it writes f back unchanged which is the idiom for testing f for zero
     let result = get_f_value(pic);
 
     update_Z(pic, result);
@@ -162,32 +284,60 @@ pub fn COMF(pic: &mut PIC10F200)  {
 pub fn INCF(pic: &mut PIC10F200)  {
     // dest <- f + 1
     let f_value = get_f_value(pic);
-    let result = f_value + 1;
+    let result = f_value.wrapping_add(1);
 
     update_Z(pic, result);
     store_wf(pic, result);
 }
 
 pub fn DECFSZ(pic: &mut PIC10F200)  {
-    todo!()
+    // dest <- f - 1, skip the next instruction if the result is 0
+    let f_value = get_f_value(pic);
+    let result = f_value.wrapping_sub(1);
+
+    store_wf(pic, result);
+    if result == 0 {
+        skip(pic);
+    }
 }
 
 pub fn RRF(pic: &mut PIC10F200)  {
-    todo!();
-    update_C();
+    // dest <- f rotated right through the carry flag
+    let f_value = get_f_value(pic);
+    let carry_in = pic.data_memory.status(Status_Masks::C) as u8;
+    let result = (f_value >> 1) | (carry_in << 7);
+
+    update_C(pic, f_value & 0x01 != 0);
+    store_wf(pic, result);
 }
 
 pub fn RLF(pic: &mut PIC10F200)  {
-    todo!();
-    update_C();
+    // dest <- f rotated left through the carry flag
+    let f_value = get_f_value(pic);
+    let carry_in = pic.data_memory.status(Status_Masks::C) as u8;
+    let result = (f_value << 1) | carry_in;
+
+    update_C(pic, f_value & 0x80 != 0);
+    store_wf(pic, result);
 }
 
 pub fn SWAPF(pic: &mut PIC10F200)  {
-    todo!()
+    // dest <- f with the nibbles swapped
+    let f_value = get_f_value(pic);
+    let result = f_value.rotate_left(4);
+
+    store_wf(pic, result);
 }
 
 pub fn INCFSZ(pic: &mut PIC10F200)  {
-    todo!()
+    // dest <- f + 1, skip the next instruction if the result is 0
+    let f_value = get_f_value(pic);
+    let result = f_value.wrapping_add(1);
+
+    store_wf(pic, result);
+    if result == 0 {
+        skip(pic);
+    }
 }
 
 /* Bit Operation */
@@ -197,10 +347,10 @@ pub fn BCF(pic: &mut PIC10F200)  {
     let instruction = pic.instruction_register;
     let f = instruction.extract_f();
     let b = instruction.extract_b();
-    let f_value = pic.data_memory.read(f);
+    let f_value = read_f(pic, f);
     let result: u8 = f_value & !(1 << b.as_u16());
 
-    pic.data_memory.write(f, result);
+    write_f(pic, f, result);
 }
 
 pub fn BSF(pic: &mut PIC10F200)  {
@@ -208,10 +358,10 @@ pub fn BSF(pic: &mut PIC10F200)  {
     let instruction = pic.instruction_register;
     let f = instruction.extract_f();
     let b = instruction.extract_b();
-    let f_value = pic.data_memory.read(f);
+    let f_value = read_f(pic, f);
     let result: u8 = f_value | (1 << b.as_u16());
 
-    pic.data_memory.write(f, result);
+    write_f(pic, f, result);
 }
 
 pub fn BTFSC(pic: &mut PIC10F200)  {
@@ -219,12 +369,12 @@ pub fn BTFSC(pic: &mut PIC10F200)  {
     let instruction = pic.instruction_register;
     let f = instruction.extract_f();
     let b = instruction.extract_b();
-    let f_value = pic.data_memory.read(f);
+    let f_value = read_f(pic, f);
     let result: u8 = f_value & (1 << b.as_u16());
 
     if result == 0 {
         // Skip the next instruction
-        pic.program_counter = pic.program_counter + u9::new(1);
+        skip(pic);
     }
 }
 
@@ -233,41 +383,43 @@ pub fn BTFSS(pic: &mut PIC10F200)  {
     let instruction = pic.instruction_register;
     let f = instruction.extract_f();
     let b = instruction.extract_b();
-    let f_value = pic.data_memory.read(f);
+    let f_value = read_f(pic, f);
     let result: u8 = f_value & (1 << b.as_u16());
 
-    if result == 1 {
+    if result != 0 {
         // Skip the next instruction
-        pic.program_counter = pic.program_counter + u9::new(1);
+        skip(pic);
     }
 }
 
 /* Control Transfers - TWO CYCLE INSTRUCTIONS, since the fetch instructino is "flushed" from the pipeline */
 //TODO: implement two cycle instructions
-//Any instruction which writes to the program counter, except GOTO, will force a 0 to be written to the 9th bit
+//Any instruction which writes to the program counter, except GOTO, will force a 0 to be written to the 9th bit, see form_pc
 
 pub fn GOTO(pic: &mut PIC10F200)  {
     // Set the program counter PC to 
     // the 9-bit address specified by the instruction
     // at k using instruction.extract_k_goto()
-    let instruction = pic.instruction_register;
-    let k = instruction.extract_k_goto();
-    pic.program_counter = k; //TODO: make sure that program counter does not increment at the end of the cycle
+    let k = pic.instruction_register.extract_k_goto();
+    jump(pic, form_pc(pic, PcSource::Goto, k.get()));
 }
 
 pub fn CALL(pic: &mut PIC10F200)  {
     //push PC + 1 onto stack and GOTO k
-    pic.program_memory.push(pic.program_counter + u9::new(1));
-    //mask out bit 8
-    pic.instruction_register.instruction_raw = pic.instruction_register.instruction_raw & u12::new(0xEFF);
-    GOTO(pic); //TODO: may not be able to use GOTO as that itself is a two cycle instruction
+    //the PC already points at the instruction after the CALL
+    pic.program_memory.push(pic.program_counter);
+    pic.stack_pushed();
+    let k = pic.instruction_register.extract_k();
+    jump(pic, form_pc(pic, PcSource::Call, k as u16));
 }
 
 pub fn RETLW(pic: &mut PIC10F200)  {
     // W <- k then RETURN()
     MOVLW(pic);
     //pop the stack and move the value to the program counter
-    pic.program_counter = pic.program_memory.pop();
+    let address = pic.program_memory.pop();
+    jump(pic, form_pc(pic, PcSource::Return, address.get()));
+    pic.stack_popped();
 }
 
 /* Operations with W */
@@ -286,6 +438,7 @@ pub fn IORLW(pic: &mut PIC10F200)  {
     let k: u8 = instruction.extract_k();
     let result = pic.w_register | k;
 
+    update_Z(pic, result);
     pic.w_register = result;
 }
 
@@ -295,6 +448,7 @@ pub fn ANDLW(pic: &mut PIC10F200)  {
     let k: u8 = instruction.extract_k();
     let result = pic.w_register & k;
 
+    update_Z(pic, result);
     pic.w_register = result;
 }
 
@@ -304,5 +458,6 @@ pub fn XORLW(pic: &mut PIC10F200)  {
     let k: u8 = instruction.extract_k();
     let result = pic.w_register ^ k;
 
+    update_Z(pic, result);
     pic.w_register = result;
 }
\ No newline at end of file
@@ -18,10 +18,27 @@
 
 #![allow(non_snake_case)]
 #![allow(non_camel_case_types)]
+#![cfg_attr(not(feature = "std"), no_std)]
+extern crate alloc;
+
 pub mod pic;
+pub mod config;
 pub mod nbitnumber;
 pub mod program_memory;
 pub mod data_memory;
 pub mod instructions;
-// Tests module
-pub mod tests;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub mod clock;
+pub mod opcodes;
+pub mod peripherals;
+pub mod disassembler;
+pub mod debugger;
+#[cfg(feature = "std")]
+pub mod tui;
+pub mod trace;
+pub mod loader;
+pub mod power;
+pub mod analysis;
+// Tests module, the tests use std throughout
+#[cfg(all(test, feature = "std"))]
+mod tests;
\ No newline at end of file
@@ -0,0 +1,214 @@
+use alloc::vec::Vec;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
+
+use crate::config::DeviceConfig;
+use crate::nbitnumber::u12;
+use crate::program_memory::BLANK_WORD;
+#[cfg(feature = "std")]
+use crate::pic::Cpu;
+
+//Words of program memory a loader fills, the largest PIC10F2xx has 512
+pub const PROGRAM_WORDS: usize = 0x200;
+
+//Word address of the configuration word, MPLAB writes it into every HEX file
+pub const CONFIG_WORD_ADDRESS: u32 = 0xFFF;
+
+//Reasons a program image could not be loaded, shared by every format
+//line numbers start at 1, addresses are word addresses
+#[derive(Debug)]
+pub enum ProgramLoadError {
+    BadChecksum { line: usize, expected: u8, found: u8 },
+    AddressOutOfRange { address: u32 },
+    TruncatedRecord { line: usize },
+    InvalidRecord { line: usize },
+    #[cfg(feature = "std")]
+    Io(io::Error),
+}
+
+impl fmt::Display for ProgramLoadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProgramLoadError::BadChecksum { line, expected, found } => {
+                write!(f, "line {}: checksum is 0x{:02X}, expected 0x{:02X}", line, found, expected)
+            }
+            ProgramLoadError::AddressOutOfRange { address } => write!(f, "word address 0x{:03X} is outside program memory", address),
+            ProgramLoadError::TruncatedRecord { line } => write!(f, "line {}: record is truncated", line),
+            ProgramLoadError::InvalidRecord { line } => write!(f, "line {}: not an Intel HEX record", line),
+            #[cfg(feature = "std")]
+            ProgramLoadError::Io(error) => write!(f, "{}", error),
+        }
+    }
+}
+
+impl core::error::Error for ProgramLoadError {
+    fn source(&self) -> Option<&(dyn core::error::Error + 'static)> {
+        match self {
+            #[cfg(feature = "std")]
+            ProgramLoadError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl From<io::Error> for ProgramLoadError {
+    fn from(error: io::Error) -> Self {
+        ProgramLoadError::Io(error)
+    }
+}
+
+//Order of the two bytes of each word in a raw binary image
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum ByteOrder {
+    //low byte first, as MPLAB exports them
+    #[default]
+    LittleEndian,
+    BigEndian,
+}
+
+//Load a .hex file as Intel HEX and anything else as a little endian binary image
+#[cfg(feature = "std")]
+pub fn load_file(path: impl AsRef<Path>) -> Result<[u12; PROGRAM_WORDS], ProgramLoadError> {
+    let path = path.as_ref();
+    let is_hex = path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("hex"));
+    if is_hex {
+        load_hex(&fs::read_to_string(path)?)
+    } else {
+        load_bin(&fs::read(path)?, ByteOrder::default())
+    }
+}
+
+//Load a file and program it into a core
+#[cfg(feature = "std")]
+pub fn program_from_file(cpu: &mut dyn Cpu, path: impl AsRef<Path>) -> Result<(), ProgramLoadError> {
+    cpu.program_chip(load_file(path)?);
+    Ok(())
+}
+
+fn store(program: &mut [u12; PROGRAM_WORDS], address: u32, word: u16) -> Result<(), ProgramLoadError> {
+    if address == CONFIG_WORD_ADDRESS {
+        //the configuration fuses are not part of program memory
+        return Ok(());
+    }
+    let slot = program.get_mut(address as usize).ok_or(ProgramLoadError::AddressOutOfRange { address })?;
+    *slot = u12::new(word);
+    Ok(())
+}
+
+//Raw image of words from address 0x000
+pub fn load_bin(bytes: &[u8], order: ByteOrder) -> Result<[u12; PROGRAM_WORDS], ProgramLoadError> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(ProgramLoadError::TruncatedRecord { line: 1 });
+    }
+    let mut program = [BLANK_WORD; PROGRAM_WORDS];
+    for (address, word) in bytes.chunks_exact(2).enumerate() {
+        let word = match order {
+            ByteOrder::LittleEndian => u16::from_le_bytes([word[0], word[1]]),
+            ByteOrder::BigEndian => u16::from_be_bytes([word[0], word[1]]),
+        };
+        store(&mut program, address as u32, word)?;
+    }
+    Ok(program)
+}
+
+//Intel HEX (INHX8M/INHX32) with byte addresses and little endian words
+pub fn load_hex(text: &str) -> Result<[u12; PROGRAM_WORDS], ProgramLoadError> {
+    let mut program = [BLANK_WORD; PROGRAM_WORDS];
+    parse_hex(text, |_, address, word| store(&mut program, address, word))?;
+    Ok(program)
+}
+
+//A reason a HEX file does not fit a device, found without programming anything
+#[derive(Debug)]
+pub enum HexIssue {
+    OutOfRange { line: usize, address: u32 },
+    Malformed(ProgramLoadError),
+}
+
+//Check every data record of a HEX file lands in the device's program memory,
+//the configuration word is allowed as it is written into every file
+pub fn validate_hex(text: &str, config: &DeviceConfig) -> Result<(), Vec<HexIssue>> {
+    let mut issues = Vec::new();
+    let parsed = parse_hex(text, |line, address, _| {
+        if address != CONFIG_WORD_ADDRESS && address >= config.program_words as u32 {
+            issues.push(HexIssue::OutOfRange { line, address });
+        }
+        Ok(())
+    });
+    if let Err(error) = parsed {
+        issues.push(HexIssue::Malformed(error));
+    }
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(issues)
+    }
+}
+
+//calls word with the line, word address and value of each word in the data records
+fn parse_hex(
+    text: &str,
+    mut word: impl FnMut(usize, u32, u16) -> Result<(), ProgramLoadError>,
+) -> Result<(), ProgramLoadError> {
+    let mut upper_address = 0u32;
+    let mut line = 0;
+
+    for (index, record) in text.lines().enumerate() {
+        line = index + 1;
+        let record = record.trim();
+        if record.is_empty() {
+            continue;
+        }
+        let bytes = parse_record(record, line)?;
+
+        let length = bytes[0] as usize;
+        if bytes.len() != length + 5 {
+            return Err(ProgramLoadError::TruncatedRecord { line });
+        }
+        let (body, checksum) = bytes.split_at(bytes.len() - 1);
+        let expected = body.iter().fold(0u8, |sum, byte| sum.wrapping_add(*byte)).wrapping_neg();
+        if checksum[0] != expected {
+            return Err(ProgramLoadError::BadChecksum { line, expected, found: checksum[0] });
+        }
+
+        let offset = u16::from_be_bytes([bytes[1], bytes[2]]) as u32;
+        let data = &body[4..];
+        match bytes[3] {
+            //data, each word is two bytes at twice its word address
+            0x00 => {
+                if !offset.is_multiple_of(2) || !data.len().is_multiple_of(2) {
+                    return Err(ProgramLoadError::InvalidRecord { line });
+                }
+                let first = (upper_address + offset) / 2;
+                for (index, bytes) in data.chunks_exact(2).enumerate() {
+                    word(line, first + index as u32, u16::from_le_bytes([bytes[0], bytes[1]]))?;
+                }
+            }
+            //end of file
+            0x01 => return Ok(()),
+            //extended segment and linear addresses
+            0x02 if data.len() == 2 => upper_address = (u16::from_be_bytes([data[0], data[1]]) as u32) << 4,
+            0x04 if data.len() == 2 => upper_address = (u16::from_be_bytes([data[0], data[1]]) as u32) << 16,
+            _ => return Err(ProgramLoadError::InvalidRecord { line }),
+        }
+    }
+    //there was no end of file record
+    Err(ProgramLoadError::TruncatedRecord { line: line + 1 })
+}
+
+fn parse_record(record: &str, line: usize) -> Result<Vec<u8>, ProgramLoadError> {
+    let digits = record.strip_prefix(':').ok_or(ProgramLoadError::InvalidRecord { line })?;
+    if !digits.is_ascii() {
+        return Err(ProgramLoadError::InvalidRecord { line });
+    }
+    //the shortest record is length, address, type and checksum
+    if digits.len() < 10 || !digits.len().is_multiple_of(2) {
+        return Err(ProgramLoadError::TruncatedRecord { line });
+    }
+    (0..digits.len())
+        .step_by(2)
+        .map(|index| u8::from_str_radix(&digits[index..index + 2], 16).map_err(|_| ProgramLoadError::InvalidRecord { line }))
+        .collect()
+}
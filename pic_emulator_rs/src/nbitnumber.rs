@@ -1,6 +1,8 @@
+use core::fmt;
+
 use derive_more::*;
 
-#[derive(Add, Sub, BitAnd, BitOr, Shl, Shr, Sum, Not, Into, PartialEq, PartialOrd, Eq)]
+#[derive(Add, Sub, BitAnd, BitOr, Shl, Shr, Sum, Not, Into, PartialEq, PartialOrd, Eq, Debug)]
 pub struct NBitNumber<const N: usize> {
     pub value: u16,
 }
@@ -10,19 +12,59 @@ pub trait NumberOperations<const N: usize> {
     fn as_u16(&self) -> u16;
     fn as_usize(&self) -> usize;
     fn get(&self) -> u16;
-    fn new() -> Self;
+    // the constructor taking a value is the inherent NBitNumber::new
+    fn zero() -> Self;
+}
+
+
+// Why try_new refused a value
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NBitError {
+    // N has to be between 1 and 15 bits to fit in the backing u16
+    InvalidWidth { bits: usize },
+    TooWide { value: u16, bits: usize },
+}
 
+impl fmt::Display for NBitError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NBitError::InvalidWidth { bits } => write!(f, "{} is not a supported bit width", bits),
+            NBitError::TooWide { value, bits } => write!(f, "0x{:X} does not fit in {} bits", value, bits),
+        }
+    }
 }
 
+impl core::error::Error for NBitError {}
 
 impl<const N: usize> NBitNumber<N> {
-    pub fn new(value: u16) -> Self {
+    pub const fn new(value: u16) -> Self {
         NBitNumber { value: value & ((1 << N) - 1)}
     }
 
-    pub fn get(&self) -> u16 {
+    // like new, but refuses values that do not fit instead of masking them, for user input
+    pub const fn try_new(value: u16) -> Result<Self, NBitError> {
+        if N == 0 || N >= 16 {
+            return Err(NBitError::InvalidWidth { bits: N });
+        }
+        if value >> N != 0 {
+            return Err(NBitError::TooWide { value, bits: N });
+        }
+        Ok(NBitNumber { value })
+    }
+
+    pub const fn get(&self) -> u16 {
         self.value
     }
+
+    // add a plain literal, wrapping at N bits like the hardware would
+    pub fn add_u16(self, rhs: u16) -> Self {
+        NBitNumber::<N>::new(self.value.wrapping_add(rhs))
+    }
+
+    // -x at N bits, i.e. the inverted value plus one
+    pub fn twos_complement(self) -> Self {
+        NBitNumber::<N>::new((!self.value).wrapping_add(1))
+    }
 }
 
 impl<const N: usize> NumberOperations<N> for NBitNumber<N> {
@@ -42,14 +84,14 @@ impl<const N: usize> NumberOperations<N> for NBitNumber<N> {
         self.value
     }
 
-    fn new() -> Self {
+    fn zero() -> Self {
         NBitNumber::<N>::new(0)
     }
 }
 
 impl<const N: usize> Clone for NBitNumber<N> {
     fn clone(&self) -> Self {
-        NBitNumber::<N>::new(self.value)
+        *self
     }
 }
 
@@ -62,4 +104,67 @@ pub type u7 = NBitNumber<7>;
 pub type u5 = NBitNumber<5>;
 pub type u9 = NBitNumber<9>;
 pub type u3 = NBitNumber<3>;
-pub type u2 = NBitNumber<2>;
\ No newline at end of file
+pub type u2 = NBitNumber<2>;
+
+// A number of any of the widths used by the instruction set
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum NBit {
+    U1(NBitNumber<1>),
+    U2(NBitNumber<2>),
+    U3(NBitNumber<3>),
+    U4(NBitNumber<4>),
+    U5(NBitNumber<5>),
+    U6(NBitNumber<6>),
+    U7(NBitNumber<7>),
+    U8(NBitNumber<8>),
+    U9(NBitNumber<9>),
+    U10(NBitNumber<10>),
+    U11(NBitNumber<11>),
+    U12(NBitNumber<12>),
+}
+
+impl NBit {
+    pub const fn as_u16(&self) -> u16 {
+        match self {
+            NBit::U1(n) => n.get(),
+            NBit::U2(n) => n.get(),
+            NBit::U3(n) => n.get(),
+            NBit::U4(n) => n.get(),
+            NBit::U5(n) => n.get(),
+            NBit::U6(n) => n.get(),
+            NBit::U7(n) => n.get(),
+            NBit::U8(n) => n.get(),
+            NBit::U9(n) => n.get(),
+            NBit::U10(n) => n.get(),
+            NBit::U11(n) => n.get(),
+            NBit::U12(n) => n.get(),
+        }
+    }
+
+    pub const fn bit_width(&self) -> usize {
+        match self {
+            NBit::U1(_) => 1,
+            NBit::U2(_) => 2,
+            NBit::U3(_) => 3,
+            NBit::U4(_) => 4,
+            NBit::U5(_) => 5,
+            NBit::U6(_) => 6,
+            NBit::U7(_) => 7,
+            NBit::U8(_) => 8,
+            NBit::U9(_) => 9,
+            NBit::U10(_) => 10,
+            NBit::U11(_) => 11,
+            NBit::U12(_) => 12,
+        }
+    }
+
+    // convert to a concrete width, clamping to its max value instead of masking
+    pub fn saturating_into<const M: usize>(&self) -> NBitNumber<M> {
+        let max = NBitNumber::<M>::get_max();
+        if self.as_u16() > max.get() {
+            max
+        } else {
+            NBitNumber::<M>::new(self.as_u16())
+        }
+    }
+}
@@ -0,0 +1,99 @@
+use crate::data_memory::Status_Masks;
+use crate::nbitnumber::{NBit, NBitNumber};
+use crate::pic::PICInstructionMnemonic;
+
+//STATUS bits an instruction may change, listed per entry in OPCODES
+pub const FLAG_C: u8 = 1 << Status_Masks::C as u8;
+pub const FLAG_DC: u8 = 1 << Status_Masks::DC as u8;
+pub const FLAG_Z: u8 = 1 << Status_Masks::Z as u8;
+pub const FLAG_PD: u8 = 1 << Status_Masks::PD as u8;
+pub const FLAG_TO: u8 = 1 << Status_Masks::TO as u8;
+
+//Every instruction is identified by the fixed bits at the top of its 12 bit word,
+//the remaining low bits hold the operands (f, d, b, k)
+pub struct Opcode {
+    pub mnemonic: PICInstructionMnemonic,
+    pub name: &'static str,
+    pub opcode: NBit,
+    pub status: u8,
+}
+
+//Entries are matched in order, so more specific opcodes must come before
+//shorter opcodes which share their fixed bits (e.g. CLRWDT before TRIS)
+//MOVLB, RETURN and RETFIE are not implemented on the PIC10F2xx
+pub const OPCODES: [Opcode; 32] = [
+    // Miscellaneous
+    Opcode { mnemonic: PICInstructionMnemonic::NOP, name: "NOP", opcode: NBit::U12(NBitNumber::new(0x000)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::OPTION, name: "OPTION", opcode: NBit::U12(NBitNumber::new(0x002)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::SLEEP, name: "SLEEP", opcode: NBit::U12(NBitNumber::new(0x003)), status: FLAG_TO | FLAG_PD },
+    Opcode { mnemonic: PICInstructionMnemonic::CLRWDT, name: "CLRWDT", opcode: NBit::U12(NBitNumber::new(0x004)), status: FLAG_TO | FLAG_PD },
+    Opcode { mnemonic: PICInstructionMnemonic::TRIS, name: "TRIS", opcode: NBit::U10(NBitNumber::new(0x001)), status: 0 },
+
+    // ALU Operation
+    Opcode { mnemonic: PICInstructionMnemonic::MOVWF, name: "MOVWF", opcode: NBit::U7(NBitNumber::new(0x01)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::CLR, name: "CLR", opcode: NBit::U6(NBitNumber::new(0x01)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::SUBWF, name: "SUBWF", opcode: NBit::U6(NBitNumber::new(0x02)), status: FLAG_C | FLAG_DC | FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::DECF, name: "DECF", opcode: NBit::U6(NBitNumber::new(0x03)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::IORWF, name: "IORWF", opcode: NBit::U6(NBitNumber::new(0x04)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::ANDWF, name: "ANDWF", opcode: NBit::U6(NBitNumber::new(0x05)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::XORWF, name: "XORWF", opcode: NBit::U6(NBitNumber::new(0x06)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::ADDWF, name: "ADDWF", opcode: NBit::U6(NBitNumber::new(0x07)), status: FLAG_C | FLAG_DC | FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::MOVF, name: "MOVF", opcode: NBit::U6(NBitNumber::new(0x08)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::COMF, name: "COMF", opcode: NBit::U6(NBitNumber::new(0x09)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::INCF, name: "INCF", opcode: NBit::U6(NBitNumber::new(0x0A)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::DECFSZ, name: "DECFSZ", opcode: NBit::U6(NBitNumber::new(0x0B)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::RRF, name: "RRF", opcode: NBit::U6(NBitNumber::new(0x0C)), status: FLAG_C },
+    Opcode { mnemonic: PICInstructionMnemonic::RLF, name: "RLF", opcode: NBit::U6(NBitNumber::new(0x0D)), status: FLAG_C },
+    Opcode { mnemonic: PICInstructionMnemonic::SWAPF, name: "SWAPF", opcode: NBit::U6(NBitNumber::new(0x0E)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::INCFSZ, name: "INCFSZ", opcode: NBit::U6(NBitNumber::new(0x0F)), status: 0 },
+
+    // Bit Operation
+    Opcode { mnemonic: PICInstructionMnemonic::BCF, name: "BCF", opcode: NBit::U4(NBitNumber::new(0x4)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::BSF, name: "BSF", opcode: NBit::U4(NBitNumber::new(0x5)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::BTFSC, name: "BTFSC", opcode: NBit::U4(NBitNumber::new(0x6)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::BTFSS, name: "BTFSS", opcode: NBit::U4(NBitNumber::new(0x7)), status: 0 },
+
+    // Control Transfer
+    Opcode { mnemonic: PICInstructionMnemonic::RETLW, name: "RETLW", opcode: NBit::U4(NBitNumber::new(0x8)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::CALL, name: "CALL", opcode: NBit::U4(NBitNumber::new(0x9)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::GOTO, name: "GOTO", opcode: NBit::U3(NBitNumber::new(0x5)), status: 0 },
+
+    // Operations with W
+    Opcode { mnemonic: PICInstructionMnemonic::MOVLW, name: "MOVLW", opcode: NBit::U4(NBitNumber::new(0xC)), status: 0 },
+    Opcode { mnemonic: PICInstructionMnemonic::IORLW, name: "IORLW", opcode: NBit::U4(NBitNumber::new(0xD)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::ANDLW, name: "ANDLW", opcode: NBit::U4(NBitNumber::new(0xE)), status: FLAG_Z },
+    Opcode { mnemonic: PICInstructionMnemonic::XORLW, name: "XORLW", opcode: NBit::U4(NBitNumber::new(0xF)), status: FLAG_Z },
+];
+
+//Shift the fixed bits of an opcode up to the top of a 12 bit word,
+//returns the aligned opcode and the mask covering the fixed bits
+pub const fn align_opcode(opcode: &NBit) -> (u16, u16) {
+    //a 12 bit opcode has no operand bits, 1 << 0 less 1 clears nothing and the mask is 0xFFF
+    let mask_bits = 12 - opcode.bit_width();
+    let mask = 0xFFF & !((1u16 << mask_bits) - 1);
+    (opcode.as_u16() << mask_bits, mask)
+}
+
+//Find the mnemonic of a word by matching the fixed bits of each entry in OPCODES in turn
+pub const fn scan_opcodes(word: u16) -> PICInstructionMnemonic {
+    let mut index = 0;
+    while index < OPCODES.len() {
+        let (opcode, mask) = align_opcode(&OPCODES[index].opcode);
+        if word & mask == opcode {
+            return OPCODES[index].mnemonic;
+        }
+        index += 1;
+    }
+    PICInstructionMnemonic::UND
+}
+
+//The mnemonic of every 12 bit word, scanned once at compile time so decoding is an index
+pub static DECODE_TABLE: [PICInstructionMnemonic; 0x1000] = {
+    let mut table = [PICInstructionMnemonic::UND; 0x1000];
+    let mut word = 0;
+    while word < table.len() {
+        table[word] = scan_opcodes(word as u16);
+        word += 1;
+    }
+    table
+};
@@ -0,0 +1,133 @@
+use crate::data_memory::{CMCON0_Masks, SpecialPurposeRegisters, Status_Masks};
+use crate::nbitnumber::{u12, u9, NumberOperations};
+use crate::pic::{HaltReason, PICInstruction, PIC10F200};
+
+/* Timer 0 */
+
+pub fn timer0_instruction_cycle(pic: &mut PIC10F200) {
+    //TMR0 counts instruction cycles unless T0CS selects the T0CKI pin
+    if !pic.option_t0cs() {
+        increment_timer0(pic);
+    }
+}
+
+pub fn timer0_external_edge(pic: &mut PIC10F200, rising: bool) {
+    if !pic.option_t0cs() {
+        return;
+    }
+    //T0SE clear counts rising edges, T0SE set counts falling edges
+    if rising != pic.option_t0se() {
+        increment_timer0(pic);
+    }
+}
+
+fn increment_timer0(pic: &mut PIC10F200) {
+    if !pic.option_psa() {
+        //the prescaler is assigned to TMR0, the rate is 1:2^(PS + 1)
+        let rate = 2u16 << pic.option_prescale().as_u16();
+        pic.prescaler += 1;
+        if pic.prescaler < rate {
+            return;
+        }
+        pic.prescaler = 0;
+    }
+
+    let tmr0 = pic.data_memory.read(SpecialPurposeRegisters::TMR0.address());
+    if tmr0 == 0xFF {
+        log::trace!("TMR0 overflow at cycle {}", pic.cycles);
+    }
+    pic.data_memory.write(SpecialPurposeRegisters::TMR0.address(), tmr0.wrapping_add(1));
+}
+
+/* Watchdog timer */
+
+pub fn watchdog_instruction_cycle(pic: &mut PIC10F200) {
+    if !pic.config.watchdog_enabled {
+        return;
+    }
+    pic.watchdog += 1;
+    if pic.watchdog < pic.config.watchdog_period {
+        return;
+    }
+    pic.watchdog = 0;
+
+    if pic.option_psa() {
+        //the prescaler is assigned to the WDT, the rate is 1:2^PS
+        let rate = 1u16 << pic.option_prescale().as_u16();
+        pic.prescaler += 1;
+        if pic.prescaler < rate {
+            return;
+        }
+        pic.prescaler = 0;
+    }
+    watchdog_reset(pic);
+}
+
+//CLRWDT and SLEEP clear the WDT, and the prescaler when it is assigned to the WDT
+pub fn clear_watchdog(pic: &mut PIC10F200) {
+    pic.watchdog = 0;
+    if pic.option_psa() {
+        pic.prescaler = 0;
+    }
+}
+
+//a WDT time-out during normal operation resets the device with TO cleared and PD unchanged
+//(TO/PD 0/u in the datasheet's table), CLRWDT sets both, SLEEP sets TO and clears PD
+//and a wake up from SLEEP keeps PD clear
+fn watchdog_reset(pic: &mut PIC10F200) {
+    log::debug!("WDT time-out at cycle {}", pic.cycles);
+    //a time-out during SLEEP is a wake up, SLEEP already cleared PD
+    if pic.halt_reason == Some(HaltReason::Sleep) {
+        pic.halt_reason = None;
+    }
+    device_reset(pic);
+    pic.data_memory.set_status(Status_Masks::TO, false);
+}
+
+//the part of a reset shared by WDT time-outs and wake ups, RAM is kept
+pub(crate) fn device_reset(pic: &mut PIC10F200) {
+    pic.program_counter = u9::new(pic.config.pc_mask());
+    pic.instruction_register = PICInstruction::from_u12(u12::new(0));
+    pic.instruction_address = None;
+    pic.option_register = 0xFF;
+    pic.tris_register = 0x0F;
+    pic.prescaler = 0;
+    pic.data_memory.write(SpecialPurposeRegisters::PCL.address(), 0xFF);
+}
+
+/* Comparator, PIC10F204/206 only */
+
+//the internal reference the inverting input uses when CNREF is clear
+pub const COMPARATOR_REFERENCE_VOLTS: f32 = 0.6;
+
+//recompute the comparator output into CMCON0, a change wakes the part from SLEEP when CWU is clear
+pub fn comparator_update(pic: &mut PIC10F200) {
+    if !pic.config.has_comparator {
+        return;
+    }
+    let [cin_plus, cin_minus] = pic.comparator_inputs;
+    //CPREF picks the pin on the non-inverting input, CNREF the inverting input
+    let non_inverting = if pic.data_memory.cmcon0(CMCON0_Masks::CPREF) { cin_plus } else { cin_minus };
+    let inverting = if pic.data_memory.cmcon0(CMCON0_Masks::CNREF) { cin_minus } else { COMPARATOR_REFERENCE_VOLTS };
+    let output = pic.data_memory.cmcon0(CMCON0_Masks::CMPON)
+        && ((non_inverting > inverting) == pic.data_memory.cmcon0(CMCON0_Masks::POL));
+
+    pic.data_memory.set_cmcon0(CMCON0_Masks::COUT, output);
+
+    let changed = output != pic.comparator_output;
+    pic.comparator_output = output;
+    let wake_enabled = !pic.data_memory.cmcon0(CMCON0_Masks::CWU);
+    if changed && wake_enabled && pic.halt_reason == Some(HaltReason::Sleep) {
+        comparator_wake(pic);
+    }
+}
+
+//waking from SLEEP is a reset, CWUF tells the firmware the comparator caused it
+fn comparator_wake(pic: &mut PIC10F200) {
+    log::debug!("comparator wake up at cycle {}", pic.cycles);
+    device_reset(pic);
+    pic.halt_reason = None;
+    pic.data_memory.set_status(Status_Masks::TO, true);
+    pic.data_memory.set_status(Status_Masks::PD, false);
+    pic.data_memory.set_status(Status_Masks::CWUF, true);
+}
@@ -1,25 +1,672 @@
-use crate::{data_memory::{RegisterFile, SpecialPurposeRegisters}, instructions::*, nbitnumber::{
+use alloc::collections::{BTreeMap, VecDeque};
+use alloc::vec::Vec;
+use core::fmt;
+
+use crate::{config::DeviceConfig, debugger::Snapshot, data_memory::{CMCON0_Masks, GPIO_Masks, OPTION_Masks, OSCCAL_Masks, RegisterDiff, RegisterFile, SpecialPurposeRegisters}, instructions::*, nbitnumber::{
     u12, u2, u3, u5, u9, NBitNumber, NumberOperations
-}, program_memory::{ProgramMemory, RESET_VECTOR}};
+}, opcodes::{align_opcode, DECODE_TABLE, OPCODES}, peripherals::{comparator_update, timer0_external_edge, timer0_instruction_cycle, watchdog_instruction_cycle}, power::PowerReport, program_memory::{ProgramMemory, RESET_VECTOR}, trace::{PeriodicCallback, Profiler, TraceHook}};
 
-#[derive(Clone, Copy)]
+//Replacement semantics for a mnemonic, the functions in instructions.rs have this type
+//so an override can wrap the default one
+pub type Executor = fn(&mut PIC10F200);
+
+//Upper bound on the number of instruction cycles run_to_halt will execute
+pub const RUN_TO_HALT_CYCLE_LIMIT: u64 = 1_000_000;
 
 //Highest level wrapper of the MCU
 pub struct PIC10F200 {
-    data_memory : RegisterFile,
-    program_memory : ProgramMemory,
-    program_counter : u9,
-    instruction_register : PICInstruction,
-    w_register : u8,
-    io_pins : [bool; 3]
+    pub(crate) config : DeviceConfig,
+    pub(crate) data_memory : RegisterFile,
+    pub(crate) program_memory : ProgramMemory,
+    pub(crate) program_counter : u9,
+    pub(crate) instruction_register : PICInstruction,
+    //address the instruction register was fetched from, None while it holds a flushed NOP
+    pub(crate) instruction_address : Option<u9>,
+    //set by an instruction which discards the fetched instruction
+    pub(crate) pipeline_flush : bool,
+    pub(crate) w_register : u8,
+    pub(crate) option_register : u8,
+    pub(crate) tris_register : u8,
+    //levels driven onto GP0-GP3 from outside, None while a pin floats
+    pub(crate) io_pins : [Option<bool>; 4],
+    //pin changes still to be applied, ordered by the cycle they happen on
+    pub(crate) stimulus : Vec<(u64, GPIO_Masks, bool)>,
+    //volts on CIN+ (GP0) and CIN- (GP1), and the last comparator output
+    pub(crate) comparator_inputs : [f32; 2],
+    pub(crate) comparator_output : bool,
+    pub(crate) prescaler : u16,
+    //instruction cycles since the WDT was last cleared
+    pub(crate) watchdog : u64,
+    pub(crate) cycles : u64,
+    //instruction cycles left before the power up timer releases reset
+    pub(crate) reset_cycles : u64,
+    pub(crate) halt_reason : Option<HaltReason>,
+    pub(crate) reset_on_halt : bool,
+    pub(crate) output_register : Option<u5>,
+    pub(crate) trap_unflashed : bool,
+    //halt on an instruction reading W before anything wrote it since power on
+    pub(crate) trap_uninitialized_w : bool,
+    pub(crate) w_written : bool,
+    //return addresses on the stack, and CALLs in a row which pushed one off the bottom
+    pub(crate) stack_depth : u8,
+    pub(crate) stack_overflows : u32,
+    pub(crate) recursion_limit : Option<u32>,
+    pub(crate) breakpoints : [bool; 0x200],
+    pub(crate) watchpoints : [bool; 0x20],
+    pub(crate) trace_hook : Option<TraceHook>,
+    pub(crate) executors : BTreeMap<PICInstructionMnemonic, Executor>,
+    pub(crate) profiler : Option<Profiler>,
+    pub(crate) periodic_callback : Option<(u64, PeriodicCallback)>,
+    pub(crate) power_report : PowerReport,
+    //state before each of the last history_depth instruction cycles, oldest first
+    pub(crate) history : VecDeque<Snapshot>,
+    pub(crate) history_depth : usize,
+}
+
+//Why the machine stopped executing instructions
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum HaltReason {
+    Sleep,
+    UndefinedInstruction,
+    CycleLimit,
+    //the instruction at this address is next to execute
+    Breakpoint(u9),
+    //this register was changed by the last instruction cycle
+    Watchpoint(u5),
+    //the instruction at this address was never flashed, only when trapping is enabled
+    UnflashedExecution(u9),
+    //more CALLs in a row overflowed the stack than the recursion trap allows
+    RunawayRecursion,
+    //the instruction at this address read W before it was written, only when trapping is enabled
+    UninitializedW(u9),
+}
+
+//What a GPIO pin can currently be used for, see PIC10F200::pin_capabilities
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PinCapabilities {
+    pub input: bool,
+    pub output: bool,
+    pub weak_pull_up: bool,
+}
+
+//Reasons the checked pin API refused an operation
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PinError {
+    //the PIC10F2xx only has GP0-GP3
+    NoSuchPin(u8),
+    //GP3 shares its pin with MCLR and can never drive
+    InputOnly(GPIO_Masks),
+    //the pin is taken by T0CKI or the comparator
+    InUseByPeripheral(GPIO_Masks),
+}
+
+impl fmt::Display for PinError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PinError::NoSuchPin(pin) => write!(f, "there is no GP{}", pin),
+            PinError::InputOnly(pin) => write!(f, "{:?} is input only", pin),
+            PinError::InUseByPeripheral(pin) => write!(f, "{:?} is in use by a peripheral", pin),
+        }
+    }
+}
+
+impl core::error::Error for PinError {}
+
+//Observable result of a headless run, used to assert program outcomes
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct ExitStatus {
+    pub cycles: u64,
+    pub w: u8,
+    //final value of the register chosen with set_output_register
+    pub output: Option<u8>,
+    pub halt_reason: HaltReason,
 }
 
+//How two machines differ, each pair is (self, other), see PIC10F200::diff
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct StateDiff {
+    pub registers: Vec<RegisterDiff>,
+    pub w: Option<(u8, u8)>,
+    pub pc: Option<(u9, u9)>,
+    //mask of the STATUS bits which differ
+    pub flags: u8,
+    pub stack: Option<([u9; 2], [u9; 2])>,
+}
+
+impl StateDiff {
+    pub fn is_empty(&self) -> bool {
+        *self == StateDiff::default()
+    }
+}
+
+//Contents of the two pipeline stages for the next instruction cycle
+#[derive(Clone, Copy)]
+pub struct PipelineState {
+    //the word the next cycle fetches, discarded if the executing instruction branches or skips
+    pub fetch_address: u9,
+    pub fetch: PICInstruction,
+    //None when the execute stage holds the NOP left by a pipeline flush
+    pub execute_address: Option<u9>,
+    pub execute: PICInstruction,
+}
+
+impl PIC10F200 {
+    pub fn new() -> Self {
+        PIC10F200::with_config(DeviceConfig::default())
+    }
+
+    pub fn with_config(config: DeviceConfig) -> Self {
+        PIC10F200 {
+            config,
+            data_memory: RegisterFile::with_config(&config),
+            program_memory: ProgramMemory::with_words(config.program_words),
+            program_counter: u9::new(0),
+            instruction_register: PICInstruction::from_u12(u12::new(0)),
+            instruction_address: None,
+            pipeline_flush: false,
+            w_register: 0,
+            option_register: 0xFF,
+            tris_register: 0x0F,
+            io_pins: [None; 4],
+            stimulus: Vec::new(),
+            comparator_inputs: [0.0; 2],
+            comparator_output: false,
+            prescaler: 0,
+            watchdog: 0,
+            cycles: 0,
+            reset_cycles: 0,
+            halt_reason: None,
+            reset_on_halt: false,
+            output_register: None,
+            trap_unflashed: false,
+            trap_uninitialized_w: false,
+            w_written: false,
+            stack_depth: 0,
+            stack_overflows: 0,
+            recursion_limit: None,
+            breakpoints: [false; 0x200],
+            watchpoints: [false; 0x20],
+            trace_hook: None,
+            executors: BTreeMap::new(),
+            profiler: None,
+            periodic_callback: None,
+            power_report: PowerReport::default(),
+            history: VecDeque::new(),
+            history_depth: 0,
+        }
+    }
+
+    pub fn config(&self) -> &DeviceConfig {
+        &self.config
+    }
+
+    //the loaded program, read only, e.g. for ProgramMemory::decoded_iter
+    pub fn program_memory(&self) -> &ProgramMemory {
+        &self.program_memory
+    }
+
+    //write the disassembly of the whole program, see ProgramMemory::write_listing
+    #[cfg(feature = "std")]
+    pub fn write_listing(&self, out: &mut dyn std::io::Write) -> std::io::Result<()> {
+        self.program_memory.write_listing(out)
+    }
+
+    //where user code starts, reached once the OSCCAL MOVLW at the top of memory has run
+    pub fn reset_vector(&self) -> u9 {
+        u9::new(RESET_VECTOR.get())
+    }
+
+    pub fn halt(&mut self, reason: HaltReason) {
+        log::debug!("halt at cycle {}: {:?}", self.cycles, reason);
+        self.halt_reason = Some(reason);
+    }
+
+    pub fn halt_reason(&self) -> Option<HaltReason> {
+        self.halt_reason
+    }
+
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
+    pub fn w_register(&self) -> u8 {
+        self.w_register
+    }
+
+    //write a file register the same way an instruction would, including INDF
+    pub fn poke(&mut self, address: u5, value: u8) {
+        self.data_memory.write(address, value);
+    }
+
+    //read a file register the same way an instruction would, including INDF
+    pub fn peek(&self, address: u5) -> u8 {
+        self.data_memory.read(address)
+    }
+
+    //drive the external level of one of the GP0-GP3 pins
+    pub fn set_input_pin(&mut self, pin: GPIO_Masks, level: bool) {
+        let pin = pin as usize;
+        let previous = self.io_pins[pin];
+        self.io_pins[pin] = Some(level);
+
+        if pin == GPIO_Masks::GP2 as usize && previous != Some(level) {
+            //GP2 doubles as the T0CKI timer 0 clock input
+            timer0_external_edge(self, level);
+        }
+    }
+
+    //look up a pin by its GPx number
+    pub fn pin(number: u8) -> Result<GPIO_Masks, PinError> {
+        match number {
+            0 => Ok(GPIO_Masks::GP0),
+            1 => Ok(GPIO_Masks::GP1),
+            2 => Ok(GPIO_Masks::GP2),
+            3 => Ok(GPIO_Masks::GP3),
+            _ => Err(PinError::NoSuchPin(number)),
+        }
+    }
+
+    //what the pin can do on this device with the current OPTION and CMCON0 settings
+    pub fn pin_capabilities(&self, pin: GPIO_Masks) -> PinCapabilities {
+        let comparator_on = self.config.has_comparator
+            && self.data_memory.cmcon0(CMCON0_Masks::CMPON);
+        let output = match pin {
+            GPIO_Masks::GP0 | GPIO_Masks::GP1 => !comparator_on,
+            GPIO_Masks::GP2 => !self.clock_out_enabled() && !self.comparator_output_on_gp2() && !self.option_t0cs(),
+            GPIO_Masks::GP3 => false,
+        };
+        PinCapabilities {
+            input: true,
+            output,
+            weak_pull_up: pin != GPIO_Masks::GP2,
+        }
+    }
+
+    //drive a pin from outside, checked variant of set_input_pin
+    pub fn drive_pin(&mut self, number: u8, level: bool) -> Result<(), PinError> {
+        let pin = PIC10F200::pin(number)?;
+        self.set_input_pin(pin, level);
+        Ok(())
+    }
+
+    //make a pin an output at level, as TRIS and a write to GPIO would
+    pub fn set_output_pin(&mut self, number: u8, level: bool) -> Result<(), PinError> {
+        let pin = PIC10F200::pin(number)?;
+        if pin == GPIO_Masks::GP3 {
+            return Err(PinError::InputOnly(pin));
+        }
+        if !self.pin_capabilities(pin).output {
+            return Err(PinError::InUseByPeripheral(pin));
+        }
+        let bit = 1 << pin as u8;
+        self.tris_register &= !bit;
+        let latch = if level { self.gpio() | bit } else { self.gpio() & !bit };
+        self.data_memory.write(SpecialPurposeRegisters::GPIO.address(), latch);
+        Ok(())
+    }
+
+    //analog levels on the comparator inputs, only the PIC10F204/206 have the comparator
+    pub fn set_comparator_inputs(&mut self, cin_plus: f32, cin_minus: f32) {
+        self.comparator_inputs = [cin_plus, cin_minus];
+        comparator_update(self);
+    }
+
+    pub fn comparator_output(&self) -> bool {
+        self.comparator_output
+    }
+
+    //COUTEN is active low, clearing it with the comparator on puts COUT on GP2 in place of the latch
+    pub fn comparator_output_on_gp2(&self) -> bool {
+        self.config.has_comparator
+            && self.data_memory.cmcon0(CMCON0_Masks::CMPON)
+            && !self.data_memory.cmcon0(CMCON0_Masks::COUTEN)
+    }
+
+    //clear TMR0, the WDT and the prescaler without a reset, RAM, the PC and OPTION are kept
+    //so peripheral tests can start over from the same program state
+    pub fn reset_peripherals(&mut self) {
+        self.data_memory.write(SpecialPurposeRegisters::TMR0.address(), 0x00);
+        self.watchdog = 0;
+        self.prescaler = 0;
+    }
+
+    //schedule input pin changes, each is applied at the start of its instruction cycle
+    pub fn apply_stimulus(&mut self, stimulus: &[(u64, GPIO_Masks, bool)]) {
+        self.stimulus.extend_from_slice(stimulus);
+        //stable, so changes on the same cycle keep their order
+        self.stimulus.sort_by_key(|(cycle, _, _)| *cycle);
+    }
+
+    fn apply_due_stimulus(&mut self) {
+        let due = self.stimulus.iter().take_while(|(cycle, _, _)| *cycle <= self.cycles).count();
+        for (_, pin, level) in self.stimulus.drain(..due).collect::<Vec<_>>() {
+            self.set_input_pin(pin, level);
+        }
+    }
+
+    //stop driving a pin, leaving it to the weak pull-up if there is one
+    pub fn float_input_pin(&mut self, pin: GPIO_Masks) {
+        self.io_pins[pin as usize] = None;
+    }
+
+    //the level driven from outside, None while the pin floats
+    pub fn input_pin(&self, pin: GPIO_Masks) -> Option<bool> {
+        self.io_pins[pin as usize]
+    }
+
+    //FOSC4 in OSCCAL puts the instruction clock on GP2, overriding TRIS, GPIO and T0CKI
+    pub fn clock_out_enabled(&self) -> bool {
+        (self.data_memory.read(SpecialPurposeRegisters::OSCCAL.address()) >> OSCCAL_Masks::FOSC4 as u8) & 0x01 == 1
+    }
+
+    //GP2 during phase 0-3 (Q1-Q4) of an instruction cycle while clock out is enabled,
+    //one period per cycle, low for Q1 and Q2 and high for Q3 and Q4
+    pub fn clock_out_level(&self, phase: u32) -> Option<bool> {
+        self.clock_out_enabled().then_some(phase % 4 >= 2)
+    }
+
+    //the level the pin reads as, None when a floating input is undefined
+    pub fn pin_level(&self, pin: GPIO_Masks) -> Option<bool> {
+        if pin == GPIO_Masks::GP2 && self.clock_out_enabled() {
+            //instructions read the port during Q2
+            return self.clock_out_level(1);
+        }
+        if pin == GPIO_Masks::GP2 && self.comparator_output_on_gp2() {
+            //comes after FOSC4 and before TRIS, writes to the GP2 latch do not reach the pin
+            return Some(self.comparator_output);
+        }
+        let pin = pin as usize;
+        //GP3 is input only
+        let is_input = pin == GPIO_Masks::GP3 as usize || (self.tris_register >> pin) & 0x01 == 1;
+        if !is_input {
+            return Some((self.gpio() >> pin) & 0x01 == 1);
+        }
+        if self.io_pins[pin].is_some() {
+            return self.io_pins[pin];
+        }
+        //GPPU is active low, GP2 has no weak pull-up
+        let pull_ups = !self.option_gppu();
+        (pull_ups && pin != GPIO_Masks::GP2 as usize).then_some(true)
+    }
+
+    //GPIO as an instruction reads it, undefined floating inputs read as 0
+    pub fn gpio_pins(&self) -> u8 {
+        [GPIO_Masks::GP0, GPIO_Masks::GP1, GPIO_Masks::GP2, GPIO_Masks::GP3]
+            .into_iter()
+            .enumerate()
+            .fold(0, |gpio, (bit, pin)| gpio | ((self.pin_level(pin) == Some(true)) as u8) << bit)
+    }
+
+    //the output latch, which MOVWF GPIO and friends write
+    pub fn gpio(&self) -> u8 {
+        self.data_memory.read(SpecialPurposeRegisters::GPIO.address())
+    }
+
+    //OPTION as the last OPTION instruction wrote it, firmware cannot read it back
+    pub fn option_register(&self) -> u8 {
+        self.option_register
+    }
+
+    fn option_bit(&self, mask: OPTION_Masks) -> bool {
+        (self.option_register >> mask as u8) & 0x01 == 1
+    }
+
+    //set disables wake up on pin change
+    pub fn option_gpwu(&self) -> bool {
+        self.option_bit(OPTION_Masks::GPWU)
+    }
+
+    //set disables the weak pull-ups
+    pub fn option_gppu(&self) -> bool {
+        self.option_bit(OPTION_Masks::GPPU)
+    }
+
+    //set clocks TMR0 from T0CKI instead of instruction cycles
+    pub fn option_t0cs(&self) -> bool {
+        self.option_bit(OPTION_Masks::T0CS)
+    }
+
+    //set counts falling T0CKI edges, clear counts rising ones
+    pub fn option_t0se(&self) -> bool {
+        self.option_bit(OPTION_Masks::T0SE)
+    }
+
+    //set assigns the prescaler to the WDT, clear to TMR0
+    pub fn option_psa(&self) -> bool {
+        self.option_bit(OPTION_Masks::PSA)
+    }
+
+    //PS2:PS0, the rate is 1:2^(PS + 1) for TMR0 and 1:2^PS for the WDT
+    pub fn option_prescale(&self) -> u3 {
+        u3::new(self.option_register as u16 & 0x07)
+    }
+
+    //when set, the machine is put back through power on reset after run_to_halt stops
+    pub fn set_reset_on_halt(&mut self, reset_on_halt: bool) {
+        self.reset_on_halt = reset_on_halt;
+    }
+
+    //report the final value of a file register from run_to_halt, for programs leaving
+    //their result in RAM rather than W
+    pub fn set_output_register(&mut self, address: Option<u5>) {
+        self.output_register = address;
+    }
+
+    //everything one instruction cycle does once tick has counted it
+    fn instruction_cycle(&mut self) {
+        if !self.stimulus.is_empty() {
+            self.apply_due_stimulus();
+        }
+        if self.reset_cycles > 0 {
+            //held in reset by the power up timer, nothing is fetched or executed
+            self.reset_cycles -= 1;
+            return;
+        }
+        let watched = self.watchpoints.contains(&true).then_some(self.data_memory);
+
+        if self.trap_unflashed && self.executing_unflashed() {
+            self.halt(HaltReason::UnflashedExecution(self.instruction_address.unwrap()));
+            return;
+        }
+        if self.trap_uninitialized_w && self.reading_uninitialized_w() {
+            let address = self.instruction_address.unwrap();
+            log::warn!("W read at 0x{:03X} before it was written", address.as_u16());
+            self.halt(HaltReason::UninitializedW(address));
+            return;
+        }
+
+        //execute the instruction fetched during the previous cycle
+        let fetch_address = self.program_counter;
+        let executed = (self.instruction_address, self.instruction_register);
+        self.execute();
+        timer0_instruction_cycle(self);
+        watchdog_instruction_cycle(self);
+        comparator_update(self);
+        if let (Some(address), instruction) = executed {
+            if self.is_tracing() {
+                self.trace(address, instruction);
+            }
+        }
+
+        self.advance_pipeline(fetch_address);
+        self.check_debug_points(watched);
+    }
+
+    fn advance_pipeline(&mut self, fetch_address: u9) {
+        //a reset moves the PC without flagging a flush
+        if core::mem::take(&mut self.pipeline_flush) || self.program_counter != fetch_address {
+            //the instruction changed the program flow, so the pipeline is flushed
+            //and the discarded fetch executes as a NOP during the next cycle
+            self.instruction_register = PICInstruction::from_u12(u12::new(0));
+            self.instruction_address = None;
+        } else {
+            self.fetch();
+        }
+    }
+
+    fn sleeping_with_watchdog(&self) -> bool {
+        self.halt_reason == Some(HaltReason::Sleep) && self.config.watchdog_enabled
+    }
+
+    //nothing outside the core can change between run_cycles' iterations: no WDT, comparator,
+    //debug points, trap, trace, profiler, periodic callback or step back history
+    fn hooks_idle(&self) -> bool {
+        !self.config.watchdog_enabled
+            && self.history_depth == 0
+            && !self.config.has_comparator
+            && !self.trap_unflashed
+            && !self.trap_uninitialized_w
+            && !self.is_tracing()
+            && self.periodic_callback.is_none()
+            && !self.breakpoints.contains(&true)
+            && !self.watchpoints.contains(&true)
+    }
+
+    //a program can start TMR0 with OPTION, so this is checked every cycle
+    fn cycle_idle(&self) -> bool {
+        let timer0_external = self.option_t0cs();
+        timer0_external && self.stimulus.is_empty() && self.reset_cycles == 0
+    }
+
+    //tick for when hooks_idle and cycle_idle hold, only the core runs
+    fn fast_cycle(&mut self) {
+        self.cycles += 1;
+        let fetch_address = self.program_counter;
+        self.execute();
+        self.advance_pipeline(fetch_address);
+    }
+
+    //halt instead of executing a word that was never flashed, off by default as
+    //program_chip flashes every word
+    pub fn set_trap_unflashed(&mut self, trap: bool) {
+        self.trap_unflashed = trap;
+    }
+
+    //halt instead of executing an instruction which reads W (MOVWF, ADDWF, OPTION, ...) when
+    //nothing has written it since power on, W only counts as written by instructions executed
+    //while the trap is on, the OSCCAL MOVLW included
+    pub fn set_trap_uninitialized_w(&mut self, trap: bool) {
+        self.trap_uninitialized_w = trap;
+    }
+
+    //run executor instead of the built in semantics whenever an instruction with this
+    //mnemonic executes, the NOP replacing a flushed fetch is not an instruction and keeps
+    //the default, the instruction still costs its usual cycles
+    pub fn set_executor(&mut self, mnemonic: PICInstructionMnemonic, executor: Executor) {
+        self.executors.insert(mnemonic, executor);
+    }
+
+    //go back to the built in semantics for the mnemonic
+    pub fn clear_executor(&mut self, mnemonic: PICInstructionMnemonic) {
+        self.executors.remove(&mnemonic);
+    }
+
+    //also records the write when the instruction about to execute loads W
+    fn reading_uninitialized_w(&mut self) -> bool {
+        if self.instruction_address.is_none() {
+            return false;
+        }
+        let instruction = self.instruction_register;
+        let uninitialized = !self.w_written && instruction.reads_w();
+        self.w_written |= instruction.writes_w();
+        uninitialized
+    }
+
+    fn executing_unflashed(&self) -> bool {
+        match self.instruction_address {
+            //the OSCCAL word at the top of memory is programmed at the factory
+            Some(address) if address.as_u16() == self.config.program_words - 1 => false,
+            Some(address) => !self.program_memory.is_flashed(address),
+            None => false,
+        }
+    }
+
+    pub fn run_cycles(&mut self, cycles: u64) {
+        let hooks_idle = self.hooks_idle();
+        for _ in 0..cycles {
+            if self.halt_reason.is_some() && !self.sleeping_with_watchdog() {
+                break;
+            }
+            if hooks_idle && self.cycle_idle() {
+                self.fast_cycle();
+            } else {
+                self.tick();
+            }
+        }
+    }
+
+    pub fn pipeline_state(&self) -> PipelineState {
+        PipelineState {
+            fetch_address: self.program_counter,
+            fetch: self.current_instruction(),
+            execute_address: self.instruction_address,
+            execute: self.instruction_register,
+        }
+    }
+
+    //run for a number of instruction cycles and report which registers changed
+    //PCL is left out since it changes every cycle as the PC advances
+    pub fn run_and_diff(&mut self, cycles: u64) -> Vec<RegisterDiff> {
+        let snapshot = self.data_memory;
+        self.run_cycles(cycles);
+        snapshot.diff(&self.data_memory)
+            .into_iter()
+            .filter(|diff| diff.address != SpecialPurposeRegisters::PCL.address())
+            .collect()
+    }
+
+    //compare the architectural state with another machine, e.g. for differential testing
+    pub fn diff(&self, other: &PIC10F200) -> StateDiff {
+        let stack = |pic: &PIC10F200| [pic.program_memory.stack()[0], pic.program_memory.stack()[1]];
+        let status = SpecialPurposeRegisters::STATUS.address();
+        StateDiff {
+            registers: self.data_memory.diff(&other.data_memory),
+            w: (self.w_register != other.w_register).then_some((self.w_register, other.w_register)),
+            pc: (self.program_counter != other.program_counter).then_some((self.program_counter, other.program_counter)),
+            flags: self.data_memory.read(status) ^ other.data_memory.read(status),
+            stack: (stack(self) != stack(other)).then_some((stack(self), stack(other))),
+        }
+    }
+
+    pub fn run_to_halt(&mut self) -> ExitStatus {
+        while self.halt_reason.is_none() {
+            if self.cycles >= RUN_TO_HALT_CYCLE_LIMIT {
+                self.halt(HaltReason::CycleLimit);
+                break;
+            }
+            self.tick();
+        }
+
+        let status = ExitStatus {
+            cycles: self.cycles,
+            w: self.w_register,
+            output: self.output_register.map(|address| self.data_memory.read(address)),
+            halt_reason: self.halt_reason.unwrap(),
+        };
+
+        //stopping at a breakpoint or watchpoint leaves the state to be inspected
+        let debug_stop = matches!(status.halt_reason, HaltReason::Breakpoint(_) | HaltReason::Watchpoint(_));
+        if self.reset_on_halt && !debug_stop {
+            self.power_on_initialize();
+        }
+        status
+    }
+}
+
+impl Default for PIC10F200 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PIC10F2Types {
     PIC10F200,
     PIC10F202,
     PIC10F204,
     PIC10F206,
 }
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
 pub enum PICInstructionType {
     Miscellaneous,
     BitOperation,
@@ -27,6 +674,7 @@ pub enum PICInstructionType {
     OperationsWithW,
     ALUOperation,
 }
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Debug)]
 pub enum PICInstructionMnemonic {
     // Miscellaneous
     NOP, CLRWDT, OPTION, RETFIE, 
@@ -51,17 +699,133 @@ pub enum PICInstructionMnemonic {
     UND
 }
 
-trait Programmable {
+impl PICInstructionMnemonic {
+    // display name, taken from the OPCODES table
+    pub fn name(&self) -> &'static str {
+        match OPCODES.iter().find(|entry| entry.mnemonic == *self) {
+            Some(entry) => entry.name,
+            // not in OPCODES as the PIC10F2xx does not implement them
+            None => match self {
+                PICInstructionMnemonic::MOVLB => "MOVLB",
+                PICInstructionMnemonic::RETURN => "RETURN",
+                PICInstructionMnemonic::RETFIE => "RETFIE",
+                _ => "UND",
+            },
+        }
+    }
+
+    // one line summary of what the instruction does, as in the datasheet's instruction set table
+    pub fn description(&self) -> &'static str {
+        use PICInstructionMnemonic::*;
+        match self {
+            NOP => "No operation",
+            CLRWDT => "Clear the watchdog timer",
+            OPTION => "Load the OPTION register from W",
+            SLEEP => "Go into standby mode",
+            TRIS => "Load the TRIS register from W",
+            MOVWF => "Move W to f",
+            CLR => "Clear W or f",
+            SUBWF => "Subtract W from f",
+            DECF => "Decrement f",
+            IORWF => "Inclusive OR W with f",
+            ANDWF => "AND W with f",
+            XORWF => "Exclusive OR W with f",
+            ADDWF => "Add W to f",
+            MOVF => "Move f",
+            COMF => "Complement f",
+            INCF => "Increment f",
+            DECFSZ => "Decrement f, skip if 0",
+            RRF => "Rotate f right through carry",
+            RLF => "Rotate f left through carry",
+            SWAPF => "Swap the nibbles of f",
+            INCFSZ => "Increment f, skip if 0",
+            BCF => "Clear bit b of f",
+            BSF => "Set bit b of f",
+            BTFSC => "Test bit b of f, skip if clear",
+            BTFSS => "Test bit b of f, skip if set",
+            GOTO => "Unconditional branch",
+            CALL => "Call subroutine",
+            RETLW => "Return, placing literal in W",
+            MOVLW => "Move literal to W",
+            IORLW => "Inclusive OR literal with W",
+            ANDLW => "AND literal with W",
+            XORLW => "Exclusive OR literal with W",
+            MOVLB | RETFIE | RETURN => "Not implemented on the PIC10F2xx",
+            UND => "Undefined instruction",
+        }
+    }
+}
+
+impl From<PICInstructionMnemonic> for &'static str {
+    fn from(mnemonic: PICInstructionMnemonic) -> Self {
+        mnemonic.name()
+    }
+}
+
+impl fmt::Display for PICInstructionMnemonic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.name())
+    }
+}
+
+pub trait Programmable {
+    //load a program and clear the register file, the device configuration is kept and the
+    //chip needs a power_on_initialize before it runs
     fn program_chip(&mut self, new_program: [u12; 0x200]);
+    //program only the given words from origin up, leaving the rest erased
+    fn program_words(&mut self, origin: u9, words: &[u12]);
 }
 impl Programmable for PIC10F200 {
     fn program_chip(&mut self, new_program: [u12; 0x200]) {
         self.program_memory.flash(new_program);
         self.data_memory.flash();
     }
+
+    fn program_words(&mut self, origin: u9, words: &[u12]) {
+        self.program_memory.flash_words(origin, words);
+        self.data_memory.flash();
+    }
+}
+
+//What the tooling (loader, disassembler, debugger front ends) needs from a core, so the
+//same tools can drive other PIC cores
+pub trait Cpu: Programmable {
+    //run one instruction cycle
+    fn step(&mut self);
+    fn reset(&mut self);
+    fn peek(&self, address: u5) -> u8;
+    fn poke(&mut self, address: u5, value: u8);
+    //address of the next instruction fetched
+    fn pc(&self) -> u9;
+    fn program_word(&self, address: u9) -> u12;
+}
+impl Cpu for PIC10F200 {
+    fn step(&mut self) {
+        self.tick();
+    }
+
+    fn reset(&mut self) {
+        self.power_on_initialize();
+    }
+
+    fn peek(&self, address: u5) -> u8 {
+        PIC10F200::peek(self, address)
+    }
+
+    fn poke(&mut self, address: u5, value: u8) {
+        PIC10F200::poke(self, address, value);
+    }
+
+    fn pc(&self) -> u9 {
+        self.program_counter
+    }
+
+    fn program_word(&self, address: u9) -> u12 {
+        self.program_memory.fetch(address)
+    }
 }
 
-trait PipelinedTuringMachine {
+pub trait PipelinedTuringMachine {
     fn power_on_initialize(&mut self);
     fn fetch(&mut self);
     fn execute(&mut self);
@@ -79,36 +843,72 @@ impl PipelinedTuringMachine for PIC10F200 {
     Fetch in Q1, Read data in Q2, Decode & Execute Q2-4, Write data in Q4
      */
     fn power_on_initialize(&mut self) {
+        //the PC starts at the top of memory, which holds the MOVLW of the OSCCAL value
+        //the pipeline starts empty, so the first cycle only fetches
+        self.program_counter = u9::new(self.config.pc_mask());
+        self.instruction_register = PICInstruction::from_u12(u12::new(0));
+        self.instruction_address = None;
+        self.pipeline_flush = false;
+        self.cycles = 0;
+        self.reset_cycles = self.config.power_up_delay;
+        self.halt_reason = None;
+        self.stack_depth = 0;
+        self.stack_overflows = 0;
+        self.w_written = false;
+
         //data sheet page 18
-        self.data_memory.write(u5::new(SpecialPurposeRegisters::PCL as u16), 0xFF);
-        self.data_memory.write(u5::new(SpecialPurposeRegisters::STATUS as u16), 0x18);
-        self.data_memory.write(u5::new(SpecialPurposeRegisters::FSR as u16), 0x70);
-        self.data_memory.write(u5::new(SpecialPurposeRegisters::OSCCAL as u16), 0xFE);
-        self.data_memory.write(u5::new(SpecialPurposeRegisters::CMCON0 as u16), 0xFF);
+        self.option_register = 0xFF;
+        self.tris_register = 0x0F;
+        self.prescaler = 0;
+        self.watchdog = 0;
+        self.data_memory.write(SpecialPurposeRegisters::PCL.address(), 0xFF);
+        self.data_memory.write(SpecialPurposeRegisters::STATUS.address(), 0x18);
+        //FSR<4:0>, and so what INDF reaches, TMR0 and the GPIO latch are undefined
+        let undefined = self.config.undefined;
+        self.data_memory.write(SpecialPurposeRegisters::FSR.address(), undefined.value(0x04) & 0x1F);
+        self.data_memory.write(SpecialPurposeRegisters::TMR0.address(), undefined.value(0x01));
+        self.data_memory.write(SpecialPurposeRegisters::GPIO.address(), undefined.value(0x06));
+        self.data_memory.write(SpecialPurposeRegisters::OSCCAL.address(), 0xFE);
+        if self.config.has_comparator {
+            self.data_memory.write(SpecialPurposeRegisters::CMCON0.address(), 0xFF);
+        }
     }
 
     fn tick(&mut self) {
-        self.fetch();
-
-        if (self.program_counter) == RESET_VECTOR {
-            //the first cycle should skip execution stage, AKA when PCL == RESET_VECTOR
+        if self.halt_reason.is_some() {
+            //asleep nothing is fetched or executed, only the WDT on its own oscillator
+            //runs and its time-out wakes the part
+            if self.sleeping_with_watchdog() {
+                self.cycles += 1;
+                watchdog_instruction_cycle(self);
+            }
             return;
         }
-        self.execute(); 
+        if self.history_depth > 0 {
+            self.record_history();
+        }
+        self.cycles += 1;
+        self.instruction_cycle();
+        if self.periodic_callback.is_some() {
+            self.run_periodic_callback();
+        }
     }
 
     fn fetch(&mut self) {
         //The PC is incremented by 1 & the instruction is placed into the instruction register every Q1 cycle
-        //if not Q1 cycle, then return
-
-        let PCL = self.data_memory.read(u5::new(SpecialPurposeRegisters::PCL as u16));
-        self.program_counter = u9::new(PCL as u16);
-
-        //Fetch the instruction from the program memory
+        log::trace!("fetch 0x{:03X}", self.program_counter.as_u16());
         self.instruction_register = PICInstruction::from_u12(self.program_memory.fetch(self.program_counter));
+        self.instruction_address = Some(self.program_counter);
+
+        //the PC wraps from the top of memory, 0xFF or 0x1FF, to the RESET_VECTOR
+        self.program_counter = u9::new((self.program_counter.as_u16() + 1) & self.config.pc_mask());
+        self.data_memory.write(SpecialPurposeRegisters::PCL.address(), self.program_counter.as_u16() as u8);
     }
 
     fn execute(&mut self) {
+        if let Some(address) = self.instruction_address {
+            log::trace!("execute 0x{:03X}: {}", address.as_u16(), self.instruction_register.decode_mnemonic());
+        }
         // Switch on Q
 
         //Decode & read data during Q2
@@ -121,76 +921,54 @@ impl PipelinedTuringMachine for PIC10F200 {
         //Write data during Q4
     }
 
-    // decoded during Q2
+    // decoded during Q2, DECODE_TABLE names the instruction and an override or the
+    // built in executor for that mnemonic runs it, the NOP of a flushed fetch is never overridden
     fn decode_mnemonic(&mut self)
     {
-        match self.instruction_register.instruction_category {
-            PICInstructionType::ALUOperation => {
-                match (self.instruction_register.instruction_raw.as_u16() & 0x3C0) >> 6 {
-                    //4 bit opcode 9 downto 6, right shifted by 6
-                    0x000 => MOVWF(self),
-                    0x001 => CLR(self),
-                    0x002 => SUBWF(self),
-                    0x003 => DECF(self),
-                    0x004 => IORWF(self),
-                    0x005 => ANDWF(self),
-                    0x006 => XORWF(self),
-                    0x007 => ADDWF(self),
-                    0x008 => MOVF(self),
-                    0x009 => COMF(self),
-                    0x00A => INCF(self),
-                    0x00B => DECF(self),
-                    0x00C => RRF(self),
-                    0x00D => RLF(self),
-                    0x00E => SWAPF(self),
-                    0x00F => INCFSZ(self),
-                    _ => HALT(self) //There should not be any undefined ALU opearations
-                }
-            }
-            PICInstructionType::BitOperation => {
-                match self.instruction_register.instruction_raw.as_u16() & (0x300) {
-                    //2 bit op code bits 9 & 8
-                    0x000 => BCF(self),
-                    0x100 => BSF(self),
-                    0x200 => BTFSC(self),
-                    0x300 => BTFSS(self),
-                    _ => HALT(self),
-                }
-            }
-            PICInstructionType::ControlTransfer => {
-                match self.instruction_register.instruction_raw.as_u16() & (0x300) {
-                    //2 bit opcode bits 9 & 8
-                    0x000 => RETLW(self),
-                    0x100 => CALL(self),
-                    0x200 | 0x300 => GOTO(self),
-                    _ => HALT(self)
-                }
-            }
-            PICInstructionType::Miscellaneous => {
-                //5 bit opcode 4 downto 0
-                match self.instruction_register.instruction_raw.as_u16() & (0x01F) {
-                    0x000 => NOP(self),
-                    0x002 => OPTION(self),
-                    0x003 => SLEEP(self),
-                    0x004 => CLRWDT(self),
-                    0x005..=0x007 => TRIS(self),
-                    _ => HALT(self),
-                }
-            }
-            PICInstructionType::OperationsWithW => {
-                match self.instruction_register.instruction_raw.as_u16() & (0x300) {
-                    //2 bit opcode 9 & 8
-                    0x000 => MOVLW(self),
-                    0x100 => IORLW(self),
-                    0x200 => ANDLW(self),
-                    0x300 => XORLW(self),
-                    _ => HALT(self),
-                }
-            }
-        };
+        let mnemonic = DECODE_TABLE[self.instruction_register.instruction_raw.as_usize()];
+        let run = self
+            .instruction_address
+            .and_then(|_| self.executors.get(&mnemonic).copied())
+            .unwrap_or_else(|| executor(mnemonic));
+        run(self);
     }
 }
 
+//Operand types, so a literal cannot be given where a register address or bit is expected
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct FileReg(pub u5);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct Literal(pub u8);
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct BitIndex(pub u3);
+
+//The operands of each instruction form, for PICInstruction::encode
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Operands {
+    None,
+    File(FileReg),
+    FileDestination(FileReg, NBitNumber<1>),
+    FileBit(FileReg, BitIndex),
+    Literal(Literal),
+    Target(u9),
+}
+
+//The operand fields of an instruction, only those its mnemonic uses are Some
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct DecodedFields {
+    pub category: PICInstructionType,
+    pub mnemonic: PICInstructionMnemonic,
+    pub f: Option<FileReg>,
+    pub d: Option<NBitNumber<1>>,
+    pub k: Option<Literal>,
+    pub b: Option<BitIndex>,
+    pub goto_target: Option<u9>,
+}
+
+//The only instruction type, pic_assembler_rs encodes against OPCODES and these
+//mnemonics instead of keeping definitions of its own
 #[derive(Clone, Copy)]
 pub struct PICInstruction  {
     pub instruction_raw: u12,
@@ -205,6 +983,77 @@ impl PICInstruction {
         }
     }
 
+    // look the mnemonic up in the table built from OPCODES
+    pub fn decode_mnemonic(&self) -> PICInstructionMnemonic {
+        DECODE_TABLE[self.instruction_raw.as_usize()]
+    }
+
+    pub fn decoded(&self) -> DecodedFields {
+        use PICInstructionMnemonic::*;
+
+        let mnemonic = self.decode_mnemonic();
+        let mut fields = DecodedFields {
+            category: self.instruction_category,
+            mnemonic,
+            f: None,
+            d: None,
+            k: None,
+            b: None,
+            goto_target: None,
+        };
+        let f = FileReg(self.extract_f());
+        match mnemonic {
+            NOP | CLRWDT | OPTION | RETFIE | SLEEP | RETURN | UND => {}
+            //TRIS names the register it loads, 5 to 7
+            TRIS => fields.f = Some(FileReg(u5::new(self.extract_f().as_u16() & 0x007))),
+            MOVLB => fields.k = Some(Literal(self.extract_k_movlb().as_u16() as u8)),
+            MOVWF => fields.f = Some(f),
+            //CLRW has no register operand
+            CLR => {
+                fields.d = Some(self.extract_d());
+                fields.f = (self.extract_d().as_u16() == 1).then_some(f);
+            }
+            SUBWF | DECF | IORWF | ANDWF | XORWF | ADDWF | MOVF | COMF | INCF | DECFSZ | RRF | RLF | SWAPF | INCFSZ => {
+                fields.f = Some(f);
+                fields.d = Some(self.extract_d());
+            }
+            BCF | BSF | BTFSC | BTFSS => {
+                fields.f = Some(f);
+                fields.b = Some(BitIndex(self.extract_b()));
+            }
+            GOTO => fields.goto_target = Some(self.extract_k_goto()),
+            CALL | RETLW | MOVLW | IORLW | ANDLW | XORLW => fields.k = Some(Literal(self.extract_k())),
+        }
+        fields
+    }
+
+    //build an instruction from its mnemonic and operands, None when the operands are not the
+    //mnemonic's form or the PIC10F2xx does not implement it
+    //CLR with no operand is CLRW and with a register is CLRF, TRIS takes 5 to 7
+    //as in the assembler a byte operation given only a register stores to it (d = 1)
+    pub fn encode(mnemonic: PICInstructionMnemonic, operands: Operands) -> Option<PICInstruction> {
+        use PICInstructionMnemonic::*;
+
+        let entry = OPCODES.iter().find(|entry| entry.mnemonic == mnemonic)?;
+        let (opcode, _) = align_opcode(&entry.opcode);
+        let operand_bits = match (mnemonic, operands) {
+            (NOP | OPTION | SLEEP | CLRWDT, Operands::None) => 0,
+            (TRIS, Operands::File(FileReg(f))) if (0x05..=0x07).contains(&f.as_u16()) => f.as_u16() & 0x003,
+            (MOVWF, Operands::File(FileReg(f))) => f.as_u16(),
+            (CLR, Operands::None) => 0,
+            (CLR, Operands::File(FileReg(f))) => 1 << 5 | f.as_u16(),
+            (SUBWF | DECF | IORWF | ANDWF | XORWF | ADDWF | MOVF | COMF | INCF | DECFSZ | RRF | RLF | SWAPF | INCFSZ,
+                Operands::FileDestination(FileReg(f), d)) => d.as_u16() << 5 | f.as_u16(),
+            (SUBWF | DECF | IORWF | ANDWF | XORWF | ADDWF | MOVF | COMF | INCF | DECFSZ | RRF | RLF | SWAPF | INCFSZ,
+                Operands::File(FileReg(f))) => 1 << 5 | f.as_u16(),
+            (BCF | BSF | BTFSC | BTFSS, Operands::FileBit(FileReg(f), BitIndex(b))) => b.as_u16() << 5 | f.as_u16(),
+            (GOTO, Operands::Target(target)) => target.as_u16(),
+            (CALL | RETLW | MOVLW | IORLW | ANDLW | XORLW, Operands::Literal(Literal(k))) => k as u16,
+            _ => return None,
+        };
+        Some(PICInstruction::from_u12(u12::new(opcode | operand_bits)))
+    }
+
     fn decode_category(instruction: u12) -> PICInstructionType {
         match instruction.as_u16() & (0xC00) {
             // misc & alu -> 0000 | 0000 | 0000
@@ -222,12 +1071,33 @@ impl PICInstruction {
         }
     }
 
+    //instructions using W as an operand, the literal ALU operations included
+    pub fn reads_w(&self) -> bool {
+        use PICInstructionMnemonic::*;
+        matches!(
+            self.decode_mnemonic(),
+            MOVWF | SUBWF | IORWF | ANDWF | XORWF | ADDWF | OPTION | TRIS | IORLW | ANDLW | XORLW
+        )
+    }
+
+    //instructions leaving a result in W, byte operations only with d = 0
+    pub fn writes_w(&self) -> bool {
+        use PICInstructionMnemonic::*;
+        match self.decode_mnemonic() {
+            MOVLW | RETLW | IORLW | ANDLW | XORLW => true,
+            MOVWF => false,
+            _ => self.decoded().d.is_some_and(|d| d.as_u16() == 0),
+        }
+    }
+
+    //the operand fields, the mnemonic itself comes from DECODE_TABLE and these are
+    //what instructions.rs and fields() read the operands through
     pub fn extract_k(&self) -> u8{
         (self.instruction_raw.as_u16() & 0x0FF) as u8
     }
 
     pub fn extract_d(&self) -> NBitNumber<1>{
-        NBitNumber::new(self.instruction_raw.as_u16() & 0x020)
+        NBitNumber::new((self.instruction_raw.as_u16() & 0x020) >> 5)
     }
 
     pub fn extract_f(&self) -> NBitNumber<5>{
@@ -0,0 +1,65 @@
+use crate::pic::{HaltReason, PIC10F200};
+
+//Coarse power state for low power analysis, the oscillator stops during SLEEP
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PowerState {
+    //executing instructions
+    Active,
+    //clocked but not executing, held in reset or stopped at a breakpoint or trap
+    Idle,
+    Sleep,
+}
+
+//Instruction cycle times spent in each power state during run_for
+#[derive(Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub struct PowerReport {
+    pub active: u64,
+    pub idle: u64,
+    pub sleep: u64,
+}
+
+impl PowerReport {
+    pub fn total(&self) -> u64 {
+        self.active + self.idle + self.sleep
+    }
+
+    //fraction of the time asleep, 0.0 before anything has run
+    pub fn sleep_fraction(&self) -> f64 {
+        match self.total() {
+            0 => 0.0,
+            total => self.sleep as f64 / total as f64,
+        }
+    }
+}
+
+impl PIC10F200 {
+    pub fn power_state(&self) -> PowerState {
+        match self.halt_reason {
+            Some(HaltReason::Sleep) => PowerState::Sleep,
+            Some(_) => PowerState::Idle,
+            None if self.reset_cycles > 0 => PowerState::Idle,
+            None => PowerState::Active,
+        }
+    }
+
+    //let cycles instruction cycle times pass, executing while awake and counting the time
+    //spent in each state, unlike run_cycles the time asleep or halted still passes
+    pub fn run_for(&mut self, cycles: u64) {
+        for _ in 0..cycles {
+            match self.power_state() {
+                PowerState::Active => self.power_report.active += 1,
+                PowerState::Idle => self.power_report.idle += 1,
+                PowerState::Sleep => self.power_report.sleep += 1,
+            }
+            self.run_cycles(1);
+        }
+    }
+
+    pub fn power_report(&self) -> PowerReport {
+        self.power_report
+    }
+
+    pub fn clear_power_report(&mut self) {
+        self.power_report = PowerReport::default();
+    }
+}
@@ -5,29 +5,141 @@
 
 //PC is 9-bits, lower 8 are stored in PCL register
 
+#[cfg(feature = "std")]
+use std::io::{self, Write};
+
+use alloc::format;
+use alloc::string::String;
+
+use crate::disassembler::disassemble;
 use crate::nbitnumber::{u12, u9, self, NumberOperations};
+use crate::pic::PICInstruction;
 
 pub const RESET_VECTOR: u12 = nbitnumber::NBitNumber { value: 0x00 };
 
+//erased flash reads 0xFFF on a real part, which decodes as XORLW 0xFF, but words a program
+//never wrote are filled with NOP here so running into unused memory does not clobber W and
+//Z on the way to the wrap at the top, set_trap_unflashed stops there instead
+//a 0xFFF that is part of an image, e.g. read back from a blank chip, runs as XORLW 0xFF
+pub const BLANK_WORD: u12 = nbitnumber::NBitNumber { value: 0x000 };
+
+#[derive(Clone, Copy)]
 pub struct ProgramMemory {
     memory: [u12; 0x200],
+    //words implemented on the device, the last of them holds the OSCCAL value
+    words: u16,
+    //words written by the last flash, the rest are still erased
+    flashed: [bool; 0x200],
     stack: [u9; 0x002]
 }
 
 impl ProgramMemory {
     pub fn new() -> Self {
+        ProgramMemory::with_words(0x100)
+    }
+
+    pub fn with_words(words: u16) -> Self {
         ProgramMemory {
-            memory: [u12::new(0); 0x200], //we only instantiate on-chip memory
+            memory: [BLANK_WORD; 0x200], //we only instantiate on-chip memory
+            words,
+            flashed: [false; 0x200],
             stack: [u9::new(0); 0x002] // for stack level 1 and 2
         }
     }
     
     pub fn fetch(&self, program_counter : u9) -> u12 {
-         self.memory[program_counter.as_usize()]
+        //words are masked so nothing above bit 11 can reach the decoder
+        u12::new(self.memory[program_counter.as_usize()].get())
     }
 
+    //replace every word, the OSCCAL word at the top included, so images have to carry it
+    //(loaders leave missing words as 0, a NOP) and every word counts as flashed
+    //the stack is not part of the flash
     pub fn flash(&mut self, new_program: [u12; 0x200])  {
-        self.memory = new_program;
+        //the value field is public, so a word may have been built without masking
+        self.memory = new_program.map(|word| u12::new(word.get()));
+        self.flashed = [true; 0x200];
+    }
+
+    //erase the whole memory then write words from origin up, only those words count as flashed
+    pub fn flash_words(&mut self, origin: u9, words: &[u12]) {
+        self.memory = [BLANK_WORD; 0x200];
+        self.flashed = [false; 0x200];
+        for (address, word) in (origin.as_usize()..0x200).zip(words) {
+            self.memory[address] = u12::new(word.get());
+            self.flashed[address] = true;
+        }
+    }
+
+    //words implemented on the device, OSCCAL included
+    pub fn words(&self) -> u16 {
+        self.words
+    }
+
+    pub fn is_flashed(&self, address: u9) -> bool {
+        self.flashed[address.as_usize()]
+    }
+
+    //words a program takes up below the OSCCAL word, anything other than a NOP and the NOPs
+    //flash_words wrote, flash marks every word so only its non-NOP words count
+    pub fn used_words(&self) -> u16 {
+        let whole_image = self.flashed.iter().all(|flashed| *flashed);
+        (0..self.words as usize - 1)
+            .filter(|address| self.memory[*address].get() != 0 || (!whole_image && self.flashed[*address]))
+            .count() as u16
+    }
+
+    //room left for the program, the OSCCAL word is not available
+    pub fn free_words(&self) -> u16 {
+        self.words - 1 - self.used_words()
+    }
+
+    //single line disassembly of one word, the factory OSCCAL word is labelled as such
+    pub fn disassemble_at(&self, address: u9) -> String {
+        let text = disassemble(self.fetch(address), None);
+        if address.as_u16() == self.words - 1 {
+            return format!("{} ; OSCCAL", text);
+        }
+        text
+    }
+
+    //.lst style listing of the words the device implements, one address, word and instruction
+    //per line, with runs of erased NOPs collapsed into a single line
+    #[cfg(feature = "std")]
+    pub fn write_listing(&self, out: &mut dyn Write) -> io::Result<()> {
+        let top = self.words - 1;
+        let mut address = 0;
+        while address <= top {
+            let word = self.fetch(u9::new(address));
+            let run = (address..top).take_while(|at| self.fetch(u9::new(*at)).get() == 0).count() as u16;
+            if run > 1 {
+                writeln!(out, "{:03X}-{:03X}  000  NOP ; {} words", address, address + run - 1, run)?;
+                address += run;
+                continue;
+            }
+            writeln!(out, "{:03X}  {:03X}  {}", address, word.get(), self.disassemble_at(u9::new(address)))?;
+            address += 1;
+        }
+        Ok(())
+    }
+
+    //each word the device implements decoded, with its address, for static analysis
+    pub fn decoded_iter(&self) -> impl Iterator<Item = (u9, PICInstruction)> + '_ {
+        (0..self.words).map(|address| (u9::new(address), PICInstruction::from_u12(self.fetch(u9::new(address)))))
+    }
+
+    //every word of program memory from address 0x000 up
+    pub fn iter(&self) -> core::slice::Iter<'_, u12> {
+        self.memory.iter()
+    }
+
+    //both stack levels, the most recent return address first
+    pub fn stack(&self) -> &[u9] {
+        &self.stack
+    }
+
+    pub fn stack_mut(&mut self) -> &mut [u9] {
+        &mut self.stack
     }
 
     pub fn pop(&mut self) -> u9 {
@@ -36,7 +148,7 @@ impl ProgramMemory {
         for i in 0..self.stack.len() - 1 {
             self.stack[i] = self.stack[i + 1];
         }
-        return value;
+        value
     }
 
     pub fn push(&mut self, value: u9) {
@@ -49,3 +161,17 @@ impl ProgramMemory {
     }
 
 }
+
+impl Default for ProgramMemory {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl From<[u12; 0x200]> for ProgramMemory {
+    fn from(program: [u12; 0x200]) -> Self {
+        let mut program_memory = ProgramMemory::new();
+        program_memory.flash(program);
+        program_memory
+    }
+}
@@ -3,3 +3,45 @@ pub mod test_pic;
 pub mod test_program_memory;
 pub mod test_data_memory;
 pub mod test_instructions;
+pub mod test_clock;
+pub mod test_opcodes;
+pub mod test_peripherals;
+pub mod test_disassembler;
+pub mod test_debugger;
+pub mod test_config;
+pub mod test_tui;
+pub mod test_trace;
+pub mod test_loader;
+pub mod test_power;
+pub mod test_analysis;
+
+#[cfg(test)]
+use crate::nbitnumber::u12;
+#[cfg(test)]
+use crate::pic::PIC10F200;
+#[cfg(test)]
+use crate::data_memory::{SpecialPurposeRegisters, Status_Masks};
+
+// Small program shared by the tests, ORs 3 and 4 together and then sleeps
+#[cfg(test)]
+pub fn sample_program() -> [u12; 0x200] {
+    let mut program = [u12::new(0); 0x200];
+    program[0x000] = u12::new(0xC03); // MOVLW 0x03
+    program[0x001] = u12::new(0x030); // MOVWF 0x10
+    program[0x002] = u12::new(0xC04); // MOVLW 0x04
+    program[0x003] = u12::new(0x110); // IORWF 0x10, W
+    program[0x004] = u12::new(0x003); // SLEEP
+    program
+}
+
+// check the STATUS flags an ALU test cares about, None skips a flag
+#[cfg(test)]
+#[track_caller]
+pub fn assert_flags(pic: &PIC10F200, c: Option<bool>, dc: Option<bool>, z: Option<bool>) {
+    let status = pic.peek(SpecialPurposeRegisters::STATUS.address());
+    for (name, expected, mask) in [("C", c, Status_Masks::C), ("DC", dc, Status_Masks::DC), ("Z", z, Status_Masks::Z)] {
+        if let Some(expected) = expected {
+            assert_eq!((status >> mask as u8) & 0x01 == 1, expected, "{} in STATUS 0x{:02X}", name, status);
+        }
+    }
+}
@@ -0,0 +1,54 @@
+#[cfg(test)]
+mod test {
+    use crate::analysis::reachability;
+    use crate::nbitnumber::{u12, u9};
+    use crate::program_memory::ProgramMemory;
+
+    fn flashed(listing: &[(usize, u16)]) -> ProgramMemory {
+        let mut program = [u12::new(0); 0x200];
+        for (address, word) in listing {
+            program[*address] = u12::new(*word);
+        }
+        let mut program_memory = ProgramMemory::new();
+        program_memory.flash(program);
+        program_memory
+    }
+
+    #[test]
+    pub fn test_dead_code_after_goto() {
+        let program = flashed(&[
+            (0x000, 0x904), // CALL sub
+            (0x001, 0xA01), // loop: GOTO loop
+            (0x002, 0xC55), // MOVLW 0x55, dead
+            (0x003, 0x031), // MOVWF 0x11, dead
+            (0x004, 0x6C3), // sub: BTFSC STATUS, 6
+            (0x005, 0x003), // SLEEP
+            (0x006, 0x800), // RETLW 0
+        ]);
+        let reachability = reachability(&program);
+        assert_eq!(reachability.dead_code(), vec![u9::new(0x002), u9::new(0x003)]);
+        assert!(reachability.is_reachable(u9::new(0x005)));
+        assert!(reachability.is_reachable(u9::new(0x006)));
+        // the erased words after the RETLW are not reached either, but are not reported
+        assert!(!reachability.is_reachable(u9::new(0x007)));
+        assert!(reachability.computed_jumps().is_empty());
+    }
+
+    #[test]
+    pub fn test_retlw_table_after_computed_jump() {
+        let program = flashed(&[
+            (0x000, 0xC01), // MOVLW 1
+            (0x001, 0x904), // CALL table
+            (0x002, 0x030), // MOVWF 0x10
+            (0x003, 0x003), // SLEEP
+            (0x004, 0x1E2), // table: ADDWF PCL, F
+            (0x005, 0x80A), // RETLW 10
+            (0x006, 0x814), // RETLW 20
+            (0x007, 0x81E), // RETLW 30
+            (0x009, 0xC00), // MOVLW 0, dead
+        ]);
+        let reachability = reachability(&program);
+        assert_eq!(reachability.computed_jumps(), [u9::new(0x004)]);
+        assert_eq!(reachability.dead_code(), vec![u9::new(0x009)]);
+    }
+}
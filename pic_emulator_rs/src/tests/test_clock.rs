@@ -0,0 +1,45 @@
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use crate::clock::SystemClock::SystemClock;
+
+    #[test]
+    pub fn test_phase_callback_order() {
+        let phases = RefCell::new(Vec::new());
+        let mut clock = SystemClock::new(4, 4_000_000, || {});
+        clock.set_phase_callback(|phase| phases.borrow_mut().push(phase));
+
+        // two instruction cycles worth of Q cycles
+        for _ in 0..8 {
+            clock.tick();
+        }
+        drop(clock);
+
+        assert_eq!(phases.into_inner(), vec![0, 1, 2, 3, 0, 1, 2, 3]);
+    }
+
+    #[test]
+    pub fn test_quadrature_clocks_follow_phase() {
+        let mut clock = SystemClock::new(4, 4_000_000, || {});
+        clock.tick();
+        assert_eq!(clock.quadrature_clocks, vec![true, false, false, false]);
+        clock.tick();
+        clock.tick();
+        clock.tick();
+        assert_eq!(clock.quadrature_clocks, vec![false, false, false, true]);
+    }
+
+    #[tokio::test(start_paused = true)]
+    pub async fn test_start_stops_after_cycle_budget() {
+        let ticks = RefCell::new(0);
+        let mut clock = SystemClock::new(4, 4_000_000, || *ticks.borrow_mut() += 1);
+        let started = tokio::time::Instant::now();
+
+        clock.start(Some(3)).await;
+        drop(clock);
+
+        // 3 instruction cycles of 4 Q cycles at 250nS each
+        assert_eq!(ticks.into_inner(), 12);
+        assert!(started.elapsed() >= std::time::Duration::from_nanos(12 * 250));
+    }
+}
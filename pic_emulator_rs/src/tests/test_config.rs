@@ -0,0 +1,67 @@
+#[cfg(test)]
+mod test {
+    use crate::config::{DeviceConfig, UndefinedPolicy};
+    use crate::data_memory::SpecialPurposeRegisters;
+    use crate::pic::{HaltReason, PIC10F200, PIC10F2Types, PipelinedTuringMachine, Programmable};
+    use crate::tests::sample_program;
+
+    #[test]
+    pub fn test_device_memory_sizes() {
+        assert_eq!(DeviceConfig::new(PIC10F2Types::PIC10F200).program_words, 256);
+        assert_eq!(DeviceConfig::new(PIC10F2Types::PIC10F206).program_words, 512);
+        assert!(DeviceConfig::new(PIC10F2Types::PIC10F204).has_comparator);
+        assert_eq!(DeviceConfig::default().device, PIC10F2Types::PIC10F200);
+    }
+
+    #[test]
+    pub fn test_power_up_delay_holds_reset() {
+        let config = DeviceConfig::default().with_power_up_delay(10);
+        let mut pic = PIC10F200::with_config(config);
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+
+        // nothing is fetched while the power up timer runs
+        pic.run_cycles(10);
+        assert_eq!(pic.program_counter.get(), 0xFF);
+        assert_eq!(pic.w_register(), 0x00);
+
+        // the sample program then takes its usual 7 cycles
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::Sleep);
+        assert_eq!(status.cycles, 17);
+        assert_eq!(status.w, 0x07);
+    }
+
+    fn undefined_registers(policy: UndefinedPolicy) -> [u8; 3] {
+        let mut pic = PIC10F200::with_config(DeviceConfig::default().with_undefined_policy(policy));
+        pic.power_on_initialize();
+        [
+            pic.peek(SpecialPurposeRegisters::TMR0.address()),
+            pic.peek(SpecialPurposeRegisters::FSR.address()),
+            pic.gpio(),
+        ]
+    }
+
+    #[test]
+    pub fn test_undefined_policy_zero() {
+        assert_eq!(undefined_registers(UndefinedPolicy::Zero), [0x00, 0xE0, 0x00]);
+        // the default is the same in this crate's tests as for every other caller
+        assert_eq!(UndefinedPolicy::default(), UndefinedPolicy::Zero);
+        assert_eq!(DeviceConfig::default().undefined, UndefinedPolicy::Zero);
+    }
+
+    #[test]
+    pub fn test_undefined_policy_fill() {
+        // FSR<7:5> always read as 1
+        assert_eq!(undefined_registers(UndefinedPolicy::Fill(0x55)), [0x55, 0xF5, 0x55]);
+        assert_eq!(undefined_registers(UndefinedPolicy::Fill(0xAA)), [0xAA, 0xEA, 0xAA]);
+    }
+
+    #[test]
+    pub fn test_undefined_policy_random() {
+        let first = undefined_registers(UndefinedPolicy::Random(7));
+        assert_eq!(undefined_registers(UndefinedPolicy::Random(7)), first);
+        assert_ne!(undefined_registers(UndefinedPolicy::Random(8)), first);
+        assert_ne!(UndefinedPolicy::Random(7).value(0x01), UndefinedPolicy::Random(7).value(0x06));
+    }
+}
@@ -1,6 +1,6 @@
 #[cfg(test)]
 mod test {
-    use crate::data_memory::{RegisterFile, SpecialPurposeRegisters};
+    use crate::data_memory::{RegisterDiff, RegisterFile, SpecialPurposeRegisters};
     use crate::nbitnumber::*;
 
     /// Test indirect addressing
@@ -17,7 +17,7 @@ mod test {
 
         assert_eq!(
             register_file.read(u5::new(SpecialPurposeRegisters::INDF as u16)),
-            register_file.read(u5::new(last_general_purpose_register as u16))
+            register_file.read(u5::new(last_general_purpose_register))
         );
            
     }
@@ -80,4 +80,187 @@ mod test {
         assert_eq!(register_file.read(u5::new(0x10)), 0x00u8);
         assert_eq!(register_file.read(u5::new(0x0F)), 0x00u8);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_special_register_addresses() {
+        assert_eq!(SpecialPurposeRegisters::INDF as u16, 0x00);
+        assert_eq!(SpecialPurposeRegisters::TMR0 as u16, 0x01);
+        assert_eq!(SpecialPurposeRegisters::PCL as u16, 0x02);
+        assert_eq!(SpecialPurposeRegisters::STATUS as u16, 0x03);
+        assert_eq!(SpecialPurposeRegisters::FSR as u16, 0x04);
+        assert_eq!(SpecialPurposeRegisters::OSCCAL as u16, 0x05);
+        assert_eq!(SpecialPurposeRegisters::GPIO as u16, 0x06);
+        assert_eq!(SpecialPurposeRegisters::CMCON0 as u16, 0x07);
+        assert_eq!(SpecialPurposeRegisters::GPIO.address(), u5::new(0x06));
+    }
+
+    #[test]
+    pub fn test_diff() {
+        let mut register_file = RegisterFile::new();
+        register_file.write(u5::new(0x10), 0xBEu8);
+        let snapshot = register_file;
+        register_file.write(u5::new(0x10), 0xEFu8);
+        register_file.write(u5::new(0x11), 0x01u8);
+
+        assert_eq!(snapshot.diff(&register_file), vec![
+            RegisterDiff { address: u5::new(0x10), old: 0xBE, new: 0xEF },
+            RegisterDiff { address: u5::new(0x11), old: 0x00, new: 0x01 },
+        ]);
+    }
+
+    #[test]
+    pub fn test_unimplemented_write_is_recorded() {
+        let mut register_file = RegisterFile::new();
+        assert_eq!(register_file.unimplemented_write(), None);
+        register_file.write(u5::new(0x0A), 0x55);
+        assert_eq!(register_file.read(u5::new(0x0A)), 0x00);
+        assert_eq!(register_file.unimplemented_write(), Some(u5::new(0x0A)));
+    }
+
+    #[test]
+    pub fn test_low_ram_on_the_512_word_parts() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let mut register_file = RegisterFile::with_config(&DeviceConfig::new(PIC10F2Types::PIC10F206));
+        register_file.write(u5::new(0x08), 0x5A);
+        assert_eq!(register_file.read(u5::new(0x08)), 0x5A);
+        assert_eq!(register_file.unimplemented_write(), None);
+
+        // the core builds its register file for the configured part
+        let mut pic = crate::pic::PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F206));
+        pic.data_memory.write(u5::new(0x08), 0xA5);
+        assert_eq!(pic.data_memory.read(u5::new(0x08)), 0xA5);
+
+        // the same address is a gap on the 10F200
+        let mut register_file = RegisterFile::new();
+        register_file.write(u5::new(0x08), 0x5A);
+        assert_eq!(register_file.read(u5::new(0x08)), 0x00);
+        assert_eq!(register_file.unimplemented_write(), Some(u5::new(0x08)));
+    }
+
+    #[test]
+    pub fn test_implemented_addresses() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let implemented = |device| RegisterFile::implemented_addresses(&DeviceConfig::new(device));
+        let map = |ranges: &[(u16, u16)]| -> Vec<u5> {
+            ranges.iter().flat_map(|&(first, last)| first..=last).map(u5::new).collect()
+        };
+        // INDF through GPIO, CMCON0 with the comparator, then the general purpose registers
+        assert_eq!(implemented(PIC10F2Types::PIC10F200), map(&[(0x00, 0x06), (0x10, 0x1F)]));
+        assert_eq!(implemented(PIC10F2Types::PIC10F202), map(&[(0x00, 0x06), (0x08, 0x1F)]));
+        assert_eq!(implemented(PIC10F2Types::PIC10F204), map(&[(0x00, 0x07), (0x10, 0x1F)]));
+        assert_eq!(implemented(PIC10F2Types::PIC10F206), map(&[(0x00, 0x1F)]));
+    }
+
+    #[test]
+    pub fn test_indirect_unimplemented_read() {
+        let mut register_file = RegisterFile::new();
+        register_file.write(u5::new(SpecialPurposeRegisters::FSR as u16), 0x0C);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::INDF.address()), 0x00);
+
+        // FSR pointing at INDF itself reads as 0
+        register_file.write(u5::new(SpecialPurposeRegisters::FSR as u16), 0x00);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::INDF.address()), 0x00);
+    }
+
+    #[test]
+    pub fn test_indirect_read_ignores_fsr_upper_bits() {
+        let mut register_file = RegisterFile::new();
+        register_file.write(u5::new(0x10), 0x42);
+        register_file.write(u5::new(SpecialPurposeRegisters::FSR as u16), 0xF0);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::INDF.address()), 0x42);
+    }
+
+    #[test]
+    pub fn test_indirect_write() {
+        let mut register_file = RegisterFile::new();
+        register_file.write(SpecialPurposeRegisters::FSR.address(), 0x1F);
+        register_file.write(SpecialPurposeRegisters::INDF.address(), 0x5A);
+        assert_eq!(register_file.read(u5::new(0x1F)), 0x5A);
+
+        // INDF writing itself changes nothing
+        register_file.write(SpecialPurposeRegisters::FSR.address(), 0x00);
+        register_file.write(SpecialPurposeRegisters::INDF.address(), 0xA5);
+        assert_eq!(register_file.read(u5::new(0x1F)), 0x5A);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::FSR.address()), 0xE0);
+    }
+
+    #[test]
+    pub fn test_fsr_upper_bits_read_as_one() {
+        let mut register_file = RegisterFile::new();
+        register_file.write(SpecialPurposeRegisters::FSR.address(), 0x00);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::FSR.address()), 0xE0);
+
+        register_file.write(SpecialPurposeRegisters::FSR.address(), 0x15);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::FSR.address()), 0xF5);
+
+        // the forced bits do not move the indirect access
+        register_file.write(u5::new(0x15), 0x3C);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::INDF.address()), 0x3C);
+    }
+
+    #[test]
+    pub fn test_indirect_wraps_at_top_of_file() {
+        let mut register_file = RegisterFile::new();
+        register_file.write(u5::new(0x1F), 0x11);
+        register_file.write(SpecialPurposeRegisters::FSR.address(), 0xFF);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::INDF.address()), 0x11);
+
+        // incrementing FSR past the last register lands back on INDF
+        let next = register_file.read(SpecialPurposeRegisters::FSR.address()).wrapping_add(1);
+        register_file.write(SpecialPurposeRegisters::FSR.address(), next);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::FSR.address()), 0xE0);
+        assert_eq!(register_file.read(SpecialPurposeRegisters::INDF.address()), 0x00);
+    }
+
+    #[test]
+    pub fn test_flash_clears_every_register() {
+        let mut register_file = RegisterFile::new();
+        for address in 0x01..0x20 {
+            register_file.write(u5::new(address), 0xA5);
+        }
+        register_file.write(u5::new(0x0A), 0x55);
+        register_file.flash();
+
+        assert!(register_file.diff(&RegisterFile::new()).is_empty());
+        assert_eq!(register_file.read(SpecialPurposeRegisters::OSCCAL.address()), 0x00);
+        assert_eq!(register_file.unimplemented_write(), None);
+    }
+
+    #[test]
+    pub fn test_status_and_cmcon0_bit_positions() {
+        use crate::data_memory::{CMCON0_Masks, Status_Masks};
+
+        let status = [
+            (Status_Masks::C, 0), (Status_Masks::DC, 1), (Status_Masks::Z, 2), (Status_Masks::PD, 3),
+            (Status_Masks::TO, 4), (Status_Masks::PA0, 5), (Status_Masks::CWUF, 6), (Status_Masks::GPWUF, 7),
+        ];
+        let mut register_file = RegisterFile::new();
+        for (bit, position) in status {
+            register_file.set_status(bit, true);
+            assert_eq!(register_file.read(SpecialPurposeRegisters::STATUS.address()), 1 << position, "{:?}", bit);
+            assert!(register_file.status(bit));
+            register_file.set_status(bit, false);
+            assert!(!register_file.status(bit));
+        }
+
+        let cmcon0 = [
+            (CMCON0_Masks::CWU, 0), (CMCON0_Masks::CPREF, 1), (CMCON0_Masks::CNREF, 2), (CMCON0_Masks::CMPON, 3),
+            (CMCON0_Masks::CMPT0CS, 4), (CMCON0_Masks::POL, 5), (CMCON0_Masks::COUTEN, 6), (CMCON0_Masks::COUT, 7),
+        ];
+        // CMCON0 is only implemented on the parts with the comparator
+        let config = crate::config::DeviceConfig::new(crate::pic::PIC10F2Types::PIC10F204);
+        let mut register_file = RegisterFile::with_config(&config);
+        register_file.write(SpecialPurposeRegisters::CMCON0.address(), 0xFF);
+        for (bit, position) in cmcon0 {
+            register_file.set_cmcon0(bit, false);
+            assert_eq!(register_file.read(SpecialPurposeRegisters::CMCON0.address()), !(1u8 << position), "{:?}", bit);
+            assert!(!register_file.cmcon0(bit));
+            register_file.set_cmcon0(bit, true);
+            assert!(register_file.cmcon0(bit));
+        }
+    }
+}
@@ -0,0 +1,304 @@
+#[cfg(test)]
+mod test {
+    use crate::nbitnumber::{u5, u9};
+    use crate::debugger::{AddressOutOfRange, StackLevelOutOfRange};
+    use crate::pic::{HaltReason, PIC10F200, PipelinedTuringMachine, Programmable};
+    use crate::tests::sample_program;
+
+    fn programmed() -> PIC10F200 {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        pic
+    }
+
+    #[test]
+    pub fn test_list_breakpoints_and_watchpoints() {
+        let mut pic = programmed();
+        pic.add_breakpoint(u9::new(0x003));
+        pic.add_breakpoint(u9::new(0x001));
+        pic.add_watchpoint(u5::new(0x10));
+
+        assert_eq!(pic.breakpoints(), vec![u9::new(0x001), u9::new(0x003)]);
+        assert_eq!(pic.watchpoints(), vec![u5::new(0x10)]);
+
+        pic.remove_breakpoint(u9::new(0x001));
+        assert_eq!(pic.breakpoints(), vec![u9::new(0x003)]);
+
+        pic.clear_all_breakpoints();
+        assert!(pic.breakpoints().is_empty());
+        assert_eq!(pic.watchpoints(), vec![u5::new(0x10)]);
+    }
+
+    #[test]
+    pub fn test_breakpoint_stops_before_instruction() {
+        let mut pic = programmed();
+        pic.add_breakpoint(u9::new(0x003));
+
+        // MOVLW 0x04 at 0x002 has run, IORWF at 0x003 has not
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::Breakpoint(u9::new(0x003)));
+        assert_eq!(pic.w_register(), 0x04);
+
+        pic.resume();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Sleep);
+        assert_eq!(pic.w_register(), 0x07);
+    }
+
+    #[test]
+    pub fn test_watchpoint_stops_after_write() {
+        let mut pic = programmed();
+        pic.add_watchpoint(u5::new(0x10));
+
+        // MOVWF 0x10 is the only write to 0x10
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::Watchpoint(u5::new(0x10)));
+        assert_eq!(pic.peek(u5::new(0x10)), 0x03);
+        assert_eq!(pic.program_counter.get(), 0x003);
+    }
+
+    fn loaded(words: &[u16]) -> PIC10F200 {
+        let mut program = sample_program();
+        for (address, word) in words.iter().enumerate() {
+            program[address] = crate::nbitnumber::u12::new(*word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        // run up to the instruction at 0x000
+        pic.run_cycles(2);
+        pic
+    }
+
+    #[test]
+    pub fn test_next_pc_sequential() {
+        let pic = loaded(&[0xC03]); // MOVLW 0x03
+        assert_eq!(pic.next_pc(), u9::new(0x001));
+        assert_eq!(pic.w_register(), 0x00);
+    }
+
+    #[test]
+    pub fn test_next_pc_goto() {
+        let pic = loaded(&[0xA42]); // GOTO 0x042
+        assert_eq!(pic.next_pc(), u9::new(0x042));
+        // nothing was executed
+        assert_eq!(pic.program_counter, u9::new(0x001));
+        assert_eq!(pic.cycles(), 2);
+    }
+
+    #[test]
+    pub fn test_next_pc_taken_skip() {
+        let mut pic = loaded(&[0x610]); // BTFSC 0x10, 0
+        assert_eq!(pic.next_pc(), u9::new(0x002));
+
+        pic.poke(u5::new(0x10), 0x01);
+        assert_eq!(pic.next_pc(), u9::new(0x001));
+    }
+
+    #[test]
+    pub fn test_next_pc_pcl_write() {
+        let mut pic = loaded(&[0x1E2]); // ADDWF PCL, F
+        pic.w_register = 0x04;
+        // PCL already holds the next fetch address
+        assert_eq!(pic.next_pc(), u9::new(0x005));
+        assert_eq!(pic.peek(crate::data_memory::SpecialPurposeRegisters::PCL.address()), 0x01);
+    }
+
+    #[test]
+    pub fn test_call_stack_after_two_calls() {
+        // CALL 0x010, then at 0x010 CALL 0x020, which loops on itself
+        let mut program = sample_program();
+        program[0x000] = crate::nbitnumber::u12::new(0x910);
+        program[0x010] = crate::nbitnumber::u12::new(0x920);
+        program[0x020] = crate::nbitnumber::u12::new(0xA20);
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.add_breakpoint(u9::new(0x020));
+        pic.run_cycles(10);
+        assert_eq!(pic.halt_reason(), Some(HaltReason::Breakpoint(u9::new(0x020))));
+        assert_eq!(pic.call_stack(), &[u9::new(0x011), u9::new(0x001)]);
+
+        pic.set_stack_entry(1, u9::new(0x042)).unwrap();
+        assert_eq!(pic.call_stack(), &[u9::new(0x011), u9::new(0x042)]);
+        assert_eq!(pic.set_stack_entry(2, u9::new(0x042)), Err(StackLevelOutOfRange(2)));
+    }
+
+    #[test]
+    pub fn test_call_stack_shrinks_after_retlw() {
+        // CALL 0x010, and at 0x010 RETLW 0x07
+        let mut program = sample_program();
+        program[0x000] = crate::nbitnumber::u12::new(0x910);
+        program[0x010] = crate::nbitnumber::u12::new(0x807);
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        assert!(pic.call_stack().is_empty());
+        pic.add_breakpoint(u9::new(0x010));
+        pic.run_cycles(10);
+        assert_eq!(pic.call_stack(), &[u9::new(0x001)]);
+
+        pic.resume();
+        pic.add_breakpoint(u9::new(0x001));
+        pic.run_cycles(10);
+        assert_eq!(pic.halt_reason(), Some(HaltReason::Breakpoint(u9::new(0x001))));
+        assert!(pic.call_stack().is_empty());
+        assert_eq!(pic.set_stack_entry(0, u9::new(0x042)), Err(StackLevelOutOfRange(0)));
+    }
+
+    fn self_call() -> PIC10F200 {
+        // CALL 0x000, forever
+        let mut program = sample_program();
+        program[0x000] = crate::nbitnumber::u12::new(0x900);
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic
+    }
+
+    #[test]
+    pub fn test_recursion_trap_trips_after_threshold() {
+        let mut pic = self_call();
+        pic.set_recursion_trap(Some(3));
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::RunawayRecursion);
+        // two CALLs of two cycles fill the stack, the fourth overflow halts in the first cycle of the sixth CALL
+        assert_eq!(status.cycles, 2 + 6 * 2 - 1);
+
+        // without the trap it runs until the cycle limit
+        let mut pic = self_call();
+        pic.run_cycles(1_000);
+        assert_eq!(pic.halt_reason(), None);
+    }
+
+    #[test]
+    pub fn test_recursion_trap_reset_by_return() {
+        // CALL sub, GOTO 0x000, sub: CALL sub2, sub2: CALL sub3, sub3: RETLW 0, the third CALL overflows
+        let mut program = sample_program();
+        let listing: [u16; 5] = [0x902, 0xA00, 0x903, 0x904, 0x800];
+        for (address, word) in listing.iter().enumerate() {
+            program[address] = crate::nbitnumber::u12::new(*word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.set_recursion_trap(Some(0));
+        pic.run_cycles(1_000);
+        assert_eq!(pic.halt_reason(), Some(HaltReason::RunawayRecursion));
+
+        // one overflow per pass is tolerated as the RETLW clears the count
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.set_recursion_trap(Some(1));
+        pic.run_cycles(1_000);
+        assert_eq!(pic.halt_reason(), None);
+    }
+
+    #[test]
+    pub fn test_current_instruction_is_word_at_pc() {
+        let mut pic = programmed();
+        pic.run_cycles(3);
+        // executing MOVWF 0x10 from 0x001 next, so the PC is on 0x002
+        assert_eq!(pic.program_counter, u9::new(0x002));
+        let current = pic.current_instruction();
+        assert_eq!(current.instruction_raw, pic.program_memory.fetch(pic.program_counter));
+        assert_eq!(current.instruction_raw, sample_program()[0x002]);
+        assert_eq!(pic.instruction_register.instruction_raw, sample_program()[0x001]);
+        // nothing ran
+        assert_eq!(pic.cycles(), 3);
+    }
+
+    #[test]
+    pub fn test_set_pc_runs_subroutine_directly() {
+        // GOTO 0x000 at the reset vector, MOVLW 0x2A, MOVWF 0x10, GOTO 0x007 from 0x005
+        let mut program = [crate::nbitnumber::u12::new(0); 0x200];
+        let listing: [(usize, u16); 4] = [(0x000, 0xA00), (0x005, 0xC2A), (0x006, 0x030), (0x007, 0xA07)];
+        for (address, word) in listing {
+            program[address] = crate::nbitnumber::u12::new(word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        assert_eq!(pic.set_pc(u9::new(0x100)), Err(AddressOutOfRange(u9::new(0x100))));
+        assert_eq!(pic.set_pc(u9::new(0x005)), Ok(()));
+        pic.run_cycles(2);
+        assert_eq!(pic.w_register(), 0x2A);
+        assert_eq!(pic.peek(u5::new(0x10)), 0x2A);
+        // GOTO 0x007 has been fetched
+        assert_eq!(pic.program_counter, u9::new(0x008));
+    }
+
+    #[test]
+    pub fn test_step_back_restores_prior_state() {
+        let mut expected = programmed();
+        expected.run_cycles(4);
+
+        let mut pic = programmed();
+        pic.set_history_depth(2);
+        pic.run_cycles(5);
+        assert!(pic.step_back());
+        assert!(pic.diff(&expected).is_empty());
+        assert_eq!(pic.cycles(), expected.cycles());
+        assert_eq!(pic.instruction_address, expected.instruction_address);
+
+        // stepping forward again replays the same cycle
+        pic.run_cycles(1);
+        expected.run_cycles(1);
+        assert!(pic.diff(&expected).is_empty());
+
+        // the history only goes back depth cycles
+        assert!(pic.step_back());
+        assert!(pic.step_back());
+        assert!(!pic.step_back());
+        assert_eq!(pic.cycles(), 3);
+    }
+
+    #[test]
+    pub fn test_step_back_restores_the_stack_but_not_the_flash() {
+        use crate::nbitnumber::u12;
+
+        // CALL 0x010
+        let mut program = sample_program();
+        program[0x000] = u12::new(0x910);
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.set_history_depth(4);
+        pic.run_cycles(3);
+        assert_eq!(pic.call_stack(), &[u9::new(0x001)]);
+
+        // a word written after the history was taken survives stepping back
+        program[0x011] = u12::new(0x003);
+        pic.program_memory.flash(program);
+        assert!(pic.step_back());
+        assert!(pic.call_stack().is_empty());
+        assert_eq!(pic.program_memory.stack()[0], u9::new(0x000));
+        assert_eq!(pic.program_memory.fetch(u9::new(0x011)), u12::new(0x003));
+    }
+
+    #[test]
+    pub fn test_estimate_cycles_to_end_of_delay() {
+        use crate::nbitnumber::u12;
+
+        // MOVLW 5, MOVWF 0x10, delay: DECFSZ 0x10, F, GOTO delay, SLEEP
+        let mut program = sample_program();
+        for (address, word) in [0xC05, 0x030, 0x2F0, 0xA02, 0x003].into_iter().enumerate() {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        // 2 cycles to fill the pipeline, 2 for the setup, 4 passes of 3 and a 2 cycle skip
+        assert_eq!(pic.estimate_cycles_to(u9::new(0x004), 100), Some(18));
+        assert_eq!(pic.cycles(), 0);
+        assert_eq!(pic.estimate_cycles_to(u9::new(0x004), 17), None);
+        // the SLEEP halts before 0x005 is reached
+        assert_eq!(pic.estimate_cycles_to(u9::new(0x005), 100), None);
+
+        pic.run_cycles(18);
+        assert_eq!(pic.pipeline_state().execute_address, Some(u9::new(0x004)));
+    }
+}
@@ -0,0 +1,33 @@
+#[cfg(test)]
+mod test {
+    use crate::disassembler::{disassemble, SymbolTable};
+    use crate::nbitnumber::u12;
+
+    #[test]
+    pub fn test_special_register_by_name() {
+        let symbols = SymbolTable::new();
+        assert_eq!(disassemble(u12::new(0x026), Some(&symbols)), "MOVWF GPIO");
+        assert_eq!(disassemble(u12::new(0x026), None), "MOVWF 0x06");
+    }
+
+    #[test]
+    pub fn test_user_symbols() {
+        let mut symbols = SymbolTable::new();
+        symbols.insert("COUNT", 0x10);
+        assert_eq!(disassemble(u12::new(0x1F0), Some(&symbols)), "ADDWF COUNT, F");
+        assert_eq!(disassemble(u12::new(0x2D0), Some(&symbols)), "DECFSZ COUNT, W");
+        assert_eq!(disassemble(u12::new(0x031), Some(&symbols)), "MOVWF 0x11");
+    }
+
+    #[test]
+    pub fn test_operand_formats() {
+        assert_eq!(disassemble(u12::new(0x000), None), "NOP");
+        assert_eq!(disassemble(u12::new(0x006), None), "TRIS 0x06");
+        assert_eq!(disassemble(u12::new(0x040), None), "CLRW");
+        assert_eq!(disassemble(u12::new(0x070), None), "CLRF 0x10");
+        assert_eq!(disassemble(u12::new(0x566), None), "BSF 0x06, 3");
+        assert_eq!(disassemble(u12::new(0xA30), None), "GOTO 0x030");
+        assert_eq!(disassemble(u12::new(0xC2A), None), "MOVLW 0x2A");
+        assert_eq!(disassemble(u12::new(0x001), None), "UND 0x001");
+    }
+}
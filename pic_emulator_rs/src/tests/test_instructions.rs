@@ -0,0 +1,522 @@
+#[cfg(test)]
+mod test {
+    use crate::data_memory::{SpecialPurposeRegisters, Status_Masks};
+    use crate::nbitnumber::{u12, u5, NBitNumber};
+    use crate::opcodes::OPCODES;
+    use crate::pic::{HaltReason, PICInstruction, PICInstructionMnemonic, PIC10F200, PipelinedTuringMachine, Programmable};
+    use crate::tests::{assert_flags, sample_program};
+
+    const W: u8 = 0x0F;
+    const F: u8 = 0x81;
+    const FILE: u16 = 0x10;
+
+    // a representative encoding of each mnemonic, operating on register 0x10 or literal 0x42
+    fn encode(mnemonic: PICInstructionMnemonic) -> u16 {
+        use PICInstructionMnemonic::*;
+        match mnemonic {
+            NOP => 0x000,
+            OPTION => 0x002,
+            SLEEP => 0x003,
+            CLRWDT => 0x004,
+            TRIS => 0x006,
+            MOVWF => 0x030,
+            CLR => 0x070,
+            SUBWF => 0x0B0,
+            DECF => 0x0F0,
+            IORWF => 0x130,
+            ANDWF => 0x170,
+            XORWF => 0x1B0,
+            ADDWF => 0x1F0,
+            MOVF => 0x210,
+            COMF => 0x270,
+            INCF => 0x2B0,
+            DECFSZ => 0x2F0,
+            RRF => 0x330,
+            RLF => 0x370,
+            SWAPF => 0x3B0,
+            INCFSZ => 0x3F0,
+            BCF => 0x4F0,   // bit 7
+            BSF => 0x530,   // bit 1
+            BTFSC => 0x630, // bit 1, clear so it skips
+            BTFSS => 0x710, // bit 0, set so it skips
+            RETLW => 0x842,
+            CALL => 0x920,
+            GOTO => 0xA30,
+            MOVLW => 0xC42,
+            IORLW => 0xD42,
+            ANDLW => 0xE42,
+            XORLW => 0xF42,
+            // not implemented on the PIC10F2xx
+            MOVLB | RETURN | RETFIE | UND => unreachable!(),
+        }
+    }
+
+    // load a single instruction at 0x000 and run up to the cycle which executes it
+    fn load(word: u16) -> PIC10F200 {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(word);
+        program[0x001] = u12::new(0xC55); // MOVLW 0x55
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.run_cycles(2);
+
+        pic.w_register = W;
+        pic.poke(u5::new(FILE), F);
+        pic
+    }
+
+    fn file(pic: &PIC10F200) -> u8 {
+        pic.peek(u5::new(FILE))
+    }
+
+    fn flag(pic: &PIC10F200, mask: Status_Masks) -> bool {
+        pic.data_memory.get_flag(NBitNumber::new(mask as u16)).get() == 1
+    }
+
+    // the next instruction was discarded from the pipeline
+    fn skipped(pic: &PIC10F200) -> bool {
+        pic.instruction_register.instruction_raw == u12::new(0x000)
+    }
+
+    fn check(mnemonic: PICInstructionMnemonic, pic: &PIC10F200) {
+        use PICInstructionMnemonic::*;
+        match mnemonic {
+            NOP => assert!(file(pic) == F && pic.w_register == W && !skipped(pic)),
+            OPTION => assert_eq!(pic.option_register, W),
+            SLEEP => {
+                assert_eq!(pic.halt_reason(), Some(HaltReason::Sleep));
+                assert!(flag(pic, Status_Masks::TO) && !flag(pic, Status_Masks::PD));
+            }
+            CLRWDT => assert!(flag(pic, Status_Masks::TO) && flag(pic, Status_Masks::PD)),
+            TRIS => assert_eq!(pic.tris_register, W),
+            MOVWF => assert_eq!(file(pic), W),
+            CLR => {
+                assert_eq!(file(pic), 0x00);
+                assert_flags(pic, None, None, Some(true));
+            }
+            SUBWF => {
+                assert_eq!(file(pic), 0x72);
+                assert_flags(pic, Some(true), Some(false), Some(false));
+            }
+            DECF => assert_eq!(file(pic), 0x80),
+            IORWF => assert_eq!(file(pic), 0x8F),
+            ANDWF => assert_eq!(file(pic), 0x01),
+            XORWF => assert_eq!(file(pic), 0x8E),
+            ADDWF => {
+                assert_eq!(file(pic), 0x90);
+                assert_flags(pic, Some(false), Some(true), Some(false));
+            }
+            MOVF => assert_eq!(pic.w_register, F),
+            COMF => assert_eq!(file(pic), 0x7E),
+            INCF => assert_eq!(file(pic), 0x82),
+            DECFSZ => assert!(file(pic) == 0x80 && !skipped(pic)),
+            RRF => assert!(file(pic) == 0x40 && flag(pic, Status_Masks::C)),
+            RLF => assert!(file(pic) == 0x02 && flag(pic, Status_Masks::C)),
+            SWAPF => assert_eq!(file(pic), 0x18),
+            INCFSZ => assert!(file(pic) == 0x82 && !skipped(pic)),
+            BCF => assert_eq!(file(pic), 0x01),
+            BSF => assert_eq!(file(pic), 0x83),
+            BTFSC => assert!(skipped(pic)),
+            BTFSS => assert!(skipped(pic)),
+            RETLW => assert!(pic.w_register == 0x42 && skipped(pic)),
+            CALL => {
+                let mut stack = pic.program_memory;
+                assert!(pic.program_counter.get() == 0x020 && stack.pop().get() == 0x001);
+            }
+            GOTO => assert!(pic.program_counter.get() == 0x030 && skipped(pic)),
+            MOVLW => assert_eq!(pic.w_register, 0x42),
+            IORLW => assert_eq!(pic.w_register, 0x4F),
+            ANDLW => assert_eq!(pic.w_register, 0x02),
+            XORLW => assert_eq!(pic.w_register, 0x4D),
+            MOVLB | RETURN | RETFIE | UND => unreachable!(),
+        }
+    }
+
+    #[test]
+    pub fn test_every_mnemonic_executes() {
+        for entry in OPCODES.iter() {
+            let word = encode(entry.mnemonic);
+            assert_eq!(PICInstruction::from_u12(u12::new(word)).decode_mnemonic(), entry.mnemonic);
+
+            let mut pic = load(word);
+            let status_before = pic.data_memory.read(SpecialPurposeRegisters::STATUS.address());
+            pic.run_cycles(1);
+            check(entry.mnemonic, &pic);
+
+            // only the flags the table lists may change
+            let status_after = pic.data_memory.read(SpecialPurposeRegisters::STATUS.address());
+            assert_eq!((status_before ^ status_after) & !entry.status, 0, "{} changed unlisted STATUS bits", entry.name);
+        }
+    }
+
+    #[test]
+    pub fn test_tris_targets_gpio_only() {
+        // TRIS 6 is TRIS GPIO
+        let mut pic = load(0x006);
+        assert_eq!(PICInstruction::from_u12(u12::new(0x006)).extract_f_tris().get(), 0x2);
+        pic.w_register = 0x05;
+        pic.run_cycles(1);
+        assert_eq!(pic.tris_register, 0x05);
+
+        // there is no port 5 or 7 to direct
+        for word in [0x005, 0x007] {
+            let mut pic = load(word);
+            let before = pic.tris_register;
+            pic.w_register = 0x05;
+            pic.run_cycles(1);
+            assert_eq!(pic.tris_register, before, "0x{:03X}", word);
+        }
+    }
+
+    #[test]
+    pub fn test_subwf_zero_w_sets_carry() {
+        // f - 0 never borrows, the carry in of the ~W + 1 add propagates out
+        let mut pic = load(encode(PICInstructionMnemonic::SUBWF));
+        pic.w_register = 0x00;
+        pic.run_cycles(1);
+        assert_eq!(file(&pic), F);
+        assert_flags(&pic, Some(true), Some(true), Some(false));
+    }
+
+    #[test]
+    pub fn test_context_save_restore() {
+        use crate::nbitnumber::u9;
+
+        // the classic ISR save/restore, SWAPF moves STATUS and W without touching Z
+        let mut program = sample_program();
+        let listing: [u16; 13] = [
+            0xC5A, // MOVLW 0x5A
+            0x503, // BSF STATUS, C
+            0x523, // BSF STATUS, DC
+            0x030, // MOVWF W_TEMP (0x10)
+            0x383, // SWAPF STATUS, W
+            0x031, // MOVWF STATUS_TEMP (0x11)
+            0x040, // CLRW, sets Z
+            0x403, // BCF STATUS, C
+            0x391, // SWAPF STATUS_TEMP, W
+            0x023, // MOVWF STATUS
+            0x3B0, // SWAPF W_TEMP, F
+            0x390, // SWAPF W_TEMP, W
+            0x003, // SLEEP
+        ];
+        for (address, word) in listing.iter().enumerate() {
+            program[address] = u12::new(*word);
+        }
+
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        let status = SpecialPurposeRegisters::STATUS.address();
+        pic.add_breakpoint(u9::new(0x003));
+        pic.add_breakpoint(u9::new(0x00C));
+
+        // the context on entry to the save
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Breakpoint(u9::new(0x003)));
+        let saved_w = pic.w_register();
+        let saved_status = pic.peek(status);
+        assert_eq!(saved_w, 0x5A);
+        assert!(flag(&pic, Status_Masks::C) && flag(&pic, Status_Masks::DC) && !flag(&pic, Status_Masks::Z));
+
+        pic.resume();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Breakpoint(u9::new(0x00C)));
+        assert_eq!(pic.w_register(), saved_w);
+        assert_eq!(pic.peek(status), saved_status);
+    }
+
+    #[test]
+    pub fn test_goto_reaches_the_upper_half_without_paging() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let mut program = sample_program();
+        program[0x000] = u12::new(0x5A3); // BSF STATUS, 5
+        program[0x001] = u12::new(0xA10); // GOTO 0x010
+        program[0x010] = u12::new(0xB10); // GOTO 0x110
+        program[0x110] = u12::new(0x910); // CALL 0x010
+
+        // STATUS<5> is reserved, GOTO 0x010 still lands in the lower half of a 512 word part
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F206));
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.run_cycles(4);
+        assert_eq!(pic.program_counter.get(), 0x010);
+
+        // GOTO reaches the upper half from its own k<8>
+        pic.run_cycles(2);
+        assert_eq!(pic.program_counter.get(), 0x110);
+
+        // CALL clears bit 8 of its target whatever STATUS<5> holds
+        pic.run_cycles(2);
+        assert_eq!(pic.program_counter.get(), 0x010);
+    }
+
+    #[test]
+    pub fn test_movwf_pcl_jumps() {
+        let mut pic = load(0x022); // MOVWF PCL
+        pic.w_register = 0x40;
+        pic.run_cycles(1);
+        assert!(pic.program_counter.get() == 0x040 && skipped(&pic));
+    }
+
+    #[test]
+    pub fn test_movwf_indf_writes_through_fsr() {
+        let mut pic = load(0x020); // MOVWF INDF
+        pic.poke(SpecialPurposeRegisters::FSR.address(), 0x12);
+        pic.run_cycles(1);
+        assert_eq!(pic.peek(u5::new(0x12)), W);
+        assert_eq!(pic.peek(SpecialPurposeRegisters::INDF.address()), W);
+    }
+
+    #[test]
+    pub fn test_status_writes_preserve_hardware_bits() {
+        let status = SpecialPurposeRegisters::STATUS.address();
+
+        // TO and PD are read only
+        let mut pic = load(0x023); // MOVWF STATUS
+        pic.w_register = 0x07;
+        pic.run_cycles(1);
+        assert_eq!(pic.peek(status), 0x1F);
+
+        // CLRF STATUS clears everything but TO and PD, then sets Z from the result
+        let mut pic = load(0x063); // CLRF STATUS
+        pic.poke(status, 0x1B); // TO, PD, DC and C set
+        pic.run_cycles(1);
+        assert_eq!(pic.peek(status), 0x1C);
+    }
+
+    #[test]
+    pub fn test_movf_to_w_sets_z() {
+        // MOVF 0x10, W
+        let mut pic = load(0x210);
+        pic.poke(u5::new(FILE), 0x00);
+        pic.run_cycles(1);
+        assert_eq!(pic.w_register, 0x00);
+        assert_flags(&pic, None, None, Some(true));
+
+        let mut pic = load(0x210);
+        pic.run_cycles(1);
+        assert_eq!(pic.w_register, F);
+        assert_flags(&pic, None, None, Some(false));
+    }
+
+    #[test]
+    pub fn test_movf_to_f_tests_register() {
+        // MOVF 0x10, F leaves W and the register alone and only reports whether it is zero
+        let mut pic = load(0x230);
+        pic.poke(u5::new(FILE), 0x00);
+        pic.run_cycles(1);
+        assert!(file(&pic) == 0x00 && pic.w_register == W);
+        assert_flags(&pic, None, None, Some(true));
+
+        let mut pic = load(0x230);
+        pic.run_cycles(1);
+        assert!(file(&pic) == F && pic.w_register == W);
+        assert_flags(&pic, None, None, Some(false));
+    }
+
+    // instruction cycles from the datasheet's instruction set summary, for the encodings above
+    fn datasheet_cycles(mnemonic: PICInstructionMnemonic) -> u64 {
+        use PICInstructionMnemonic::*;
+        match mnemonic {
+            GOTO | CALL | RETLW => 2,
+            // both encodings above skip
+            BTFSC | BTFSS => 2,
+            _ => 1,
+        }
+    }
+
+    // cycles until the next fetched instruction reaches the execute stage
+    fn measured_cycles(mut pic: PIC10F200) -> u64 {
+        let start = pic.cycles();
+        pic.run_cycles(1);
+        while pic.halt_reason().is_none() && pic.instruction_address.is_none() {
+            pic.run_cycles(1);
+        }
+        pic.cycles() - start
+    }
+
+    #[test]
+    pub fn test_every_mnemonic_takes_datasheet_cycles() {
+        for entry in OPCODES.iter() {
+            let cycles = measured_cycles(load(encode(entry.mnemonic)));
+            assert_eq!(cycles, datasheet_cycles(entry.mnemonic), "{} took {} cycles", entry.name, cycles);
+        }
+    }
+
+    #[test]
+    pub fn test_skip_and_pcl_write_timing() {
+        // not taken
+        assert_eq!(measured_cycles(load(0x730)), 1); // BTFSS 0x10, 1
+        assert_eq!(measured_cycles(load(0x610)), 1); // BTFSC 0x10, 0
+        // DECFSZ and INCFSZ take two cycles when the result is zero
+        let mut pic = load(0x2F0); // DECFSZ 0x10, F
+        pic.poke(u5::new(FILE), 0x01);
+        assert_eq!(measured_cycles(pic), 2);
+        let mut pic = load(0x3F0); // INCFSZ 0x10, F
+        pic.poke(u5::new(FILE), 0xFF);
+        assert_eq!(measured_cycles(pic), 2);
+        // a write to PCL is a jump
+        assert_eq!(measured_cycles(load(0x022)), 2); // MOVWF PCL
+    }
+
+    #[test]
+    pub fn test_addwf_pcl_wraps_at_page_boundary() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let mut program = [u12::new(0); 0x200];
+        program[0x000] = u12::new(0xC05); // MOVLW 5
+        program[0x001] = u12::new(0xAFC); // GOTO table
+        program[0x002] = u12::new(0xCAA); // MOVLW 0xAA
+        program[0x003] = u12::new(0x003); // SLEEP
+        program[0x0FC] = u12::new(0x1E2); // table: ADDWF PCL, F
+        program[0x102] = u12::new(0xCBB); // MOVLW 0xBB, where a 9 bit add would land
+        program[0x103] = u12::new(0x003); // SLEEP
+
+        // PCL holds 0xFD when the ADDWF runs, 0xFD + 5 carries out of the low byte
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F202));
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        let status = pic.run_to_halt();
+        assert_eq!(status.w, 0xAA);
+        assert!(flag(&pic, Status_Masks::C));
+    }
+
+    #[test]
+    pub fn test_call_retlw_table_lookup() {
+        let mut program = sample_program();
+        let listing: [u16; 8] = [
+            0xC02, // MOVLW 2
+            0x904, // CALL table
+            0x030, // MOVWF 0x10
+            0x003, // SLEEP
+            0x1E2, // table: ADDWF PCL, F
+            0x840, // RETLW 0x40
+            0x841, // RETLW 0x41
+            0x842, // RETLW 0x42
+        ];
+        for (address, word) in listing.iter().enumerate() {
+            program[address] = u12::new(*word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::Sleep);
+        assert_eq!(status.w, 0x42);
+        assert_eq!(file(&pic), 0x42);
+        // the return address was popped
+        assert_eq!(pic.program_memory.stack()[0].get(), 0x000);
+    }
+
+    #[test]
+    pub fn test_alu_write_to_pcl_costs_two_cycles() {
+        // every write to PCL flushes the pipeline like a branch
+        assert_eq!(measured_cycles(load(0x1E2)), 2); // ADDWF PCL, F
+        assert_eq!(measured_cycles(load(0x0A2)), 2); // SUBWF PCL, F
+        assert_eq!(measured_cycles(load(0x502)), 2); // BSF PCL, 0, which is already set
+        // even when the PC ends up where it was, as with GOTO $+1
+        assert_eq!(measured_cycles(load(0xA01)), 2); // GOTO 0x001
+        // reading PCL into W does not
+        assert_eq!(measured_cycles(load(0x1C2)), 1); // ADDWF PCL, W
+        assert_eq!(measured_cycles(load(0x202)), 1); // MOVF PCL, W
+    }
+
+    #[test]
+    pub fn test_form_pc_per_source() {
+        use crate::config::DeviceConfig;
+        use crate::instructions::{form_pc, PcSource};
+        use crate::pic::PIC10F2Types;
+
+        let pc = |pic: &PIC10F200, source: PcSource, value: u16| form_pc(pic, source, value).get();
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F202));
+        pic.power_on_initialize();
+
+        // GOTO keeps all 9 bits, CALL and PCL writes clear PC<8>
+        assert_eq!(pc(&pic, PcSource::Goto, 0x155), 0x155);
+        assert_eq!(pc(&pic, PcSource::Call, 0x1AA), 0x0AA);
+        assert_eq!(pc(&pic, PcSource::PclWrite, 0x1FD), 0x0FD);
+        assert_eq!(pc(&pic, PcSource::Return, 0x1AB), 0x1AB);
+
+        // there is no paging, setting STATUS<5> leaves every source as the opcode formed it
+        pic.data_memory.set_flag(NBitNumber::new(Status_Masks::PA0 as u16), NBitNumber::new(1));
+        assert_eq!(pc(&pic, PcSource::Goto, 0x055), 0x055);
+        assert_eq!(pc(&pic, PcSource::Goto, 0x155), 0x155);
+        assert_eq!(pc(&pic, PcSource::Call, 0x0AA), 0x0AA);
+        assert_eq!(pc(&pic, PcSource::PclWrite, 0x0FD), 0x0FD);
+
+        // the 256 word parts drop PC<8> as well
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        assert_eq!(pc(&pic, PcSource::Goto, 0x155), 0x055);
+        assert_eq!(pc(&pic, PcSource::Call, 0x0AA), 0x0AA);
+    }
+
+    #[test]
+    pub fn test_call_goto_and_pcl_write_form_the_pc() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let run = |word: u16| {
+            let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F202));
+            let mut program = sample_program();
+            program[0x000] = u12::new(word);
+            program[0x1FF] = u12::new(0xCFE); // MOVLW 0xFE, the OSCCAL word
+            pic.program_chip(program);
+            pic.power_on_initialize();
+            pic.run_cycles(3);
+            pic.program_counter.get()
+        };
+        // GOTO 0x155, CALL 0x0AA, and MOVWF PCL with the OSCCAL value in W
+        assert_eq!(run(0xB55), 0x155);
+        assert_eq!(run(0x9AA), 0x0AA);
+        assert_eq!(run(0x022), 0x0FE);
+    }
+
+    #[test]
+    pub fn test_reading_pcl_gives_the_next_address() {
+        use crate::config::DeviceConfig;
+        use crate::nbitnumber::u9;
+        use crate::pic::PIC10F2Types;
+
+        // NOP, MOVF PCL, W, GOTO 0x010, and at 0x010 MOVF PCL, W then SLEEP
+        let mut program = sample_program();
+        for (address, word) in [(0x000, 0x000), (0x001, 0x202), (0x002, 0xA10), (0x010, 0x202), (0x011, 0x003)] {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.run_cycles(4);
+        assert_eq!(pic.w_register, 0x02);
+        assert_eq!(pic.run_to_halt().w, 0x11);
+
+        // only the low byte of the PC on the upper page of a 512 word part, through INDF too
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F202));
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        pic.program_words(u9::new(0x1F0), &[u12::new(0xC02), u12::new(0x024), u12::new(0x200), u12::new(0x003)]);
+        pic.set_pc(u9::new(0x1F0)).unwrap();
+        assert_eq!(pic.run_to_halt().w, 0xF3);
+    }
+
+    #[test]
+    pub fn test_skip_wraps_at_top_of_program_memory() {
+        use crate::nbitnumber::u9;
+
+        // BTFSS 0x10, 0 at 0x0FE skips 0x0FF and lands on 0x000, not past the 256 words
+        let mut program = sample_program();
+        program[0x0FE] = u12::new(0x710);
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.poke(u5::new(FILE), 0x01);
+        pic.set_pc(u9::new(0x0FE)).unwrap();
+        pic.run_cycles(2);
+        assert_eq!(pic.instruction_address, Some(u9::new(0x000)));
+        assert_eq!(pic.run_to_halt().w, 0x07);
+    }
+}
@@ -0,0 +1,129 @@
+#[cfg(test)]
+mod test {
+    use crate::loader::{load_bin, load_file, load_hex, validate_hex, ByteOrder, HexIssue, ProgramLoadError};
+    use crate::tests::sample_program;
+
+    // the first five words of the sample program and the configuration word
+    const SAMPLE_HEX: &str = "\
+:0A000000030C3000040C1001030093
+:021FFE00EB0FE7
+:00000001FF
+";
+
+    #[test]
+    pub fn test_load_hex() {
+        let program = load_hex(SAMPLE_HEX).unwrap();
+        assert_eq!(program, sample_program());
+    }
+
+    #[test]
+    pub fn test_load_bin() {
+        let bytes = [0x03, 0x0C, 0x30, 0x00, 0x04, 0x0C, 0x10, 0x01, 0x03, 0x00];
+        assert_eq!(load_bin(&bytes, ByteOrder::default()).unwrap(), sample_program());
+    }
+
+    #[test]
+    pub fn test_load_bin_both_byte_orders() {
+        let little = [0x03, 0x0C, 0x30, 0x00, 0x04, 0x0C, 0x10, 0x01, 0x03, 0x00];
+        let big = [0x0C, 0x03, 0x00, 0x30, 0x0C, 0x04, 0x01, 0x10, 0x00, 0x03];
+        assert_eq!(load_bin(&little, ByteOrder::LittleEndian).unwrap(), sample_program());
+        assert_eq!(load_bin(&big, ByteOrder::BigEndian).unwrap(), sample_program());
+        assert_eq!(ByteOrder::default(), ByteOrder::LittleEndian);
+    }
+
+    #[test]
+    pub fn test_bad_checksum() {
+        let error = load_hex(":0A000000030C3000040C1001030094\n:00000001FF").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::BadChecksum { line: 1, expected: 0x93, found: 0x94 }));
+    }
+
+    #[test]
+    pub fn test_address_out_of_range() {
+        // one word at byte address 0x0400, word address 0x200
+        let error = load_hex(":02040000FF0FEC\n:00000001FF").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::AddressOutOfRange { address: 0x200 }));
+
+        let error = load_bin(&[0x00; 0x402], ByteOrder::LittleEndian).unwrap_err();
+        assert!(matches!(error, ProgramLoadError::AddressOutOfRange { address: 0x200 }));
+    }
+
+    #[test]
+    pub fn test_validate_hex_against_device() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let pic10f200 = DeviceConfig::new(PIC10F2Types::PIC10F200);
+        let pic10f206 = DeviceConfig::new(PIC10F2Types::PIC10F206);
+        assert!(validate_hex(SAMPLE_HEX, &pic10f200).is_ok());
+
+        // one word at word address 0x300, past even the 512 word parts
+        let issues = validate_hex(":02060000FF0FEA\n:00000001FF", &pic10f200).unwrap_err();
+        assert!(matches!(issues.as_slice(), [HexIssue::OutOfRange { line: 1, address: 0x300 }]));
+
+        // word 0x180 only fits the 512 word parts
+        let upper_page = ":02030000FF0FED\n:00000001FF";
+        assert!(validate_hex(upper_page, &pic10f206).is_ok());
+        assert!(matches!(validate_hex(upper_page, &pic10f200).unwrap_err().as_slice(), [HexIssue::OutOfRange { address: 0x180, .. }]));
+
+        let issues = validate_hex(":0A000000030C3000040C1001030094\n:00000001FF", &pic10f200).unwrap_err();
+        assert!(matches!(issues.as_slice(), [HexIssue::Malformed(ProgramLoadError::BadChecksum { line: 1, .. })]));
+    }
+
+    #[test]
+    pub fn test_truncated_record() {
+        // the length says 10 bytes but only 8 follow
+        let error = load_hex(":0A000000030C3000040C10\n:00000001FF").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::TruncatedRecord { line: 1 }));
+
+        // no end of file record
+        let error = load_hex(":0A000000030C3000040C1001030093\n").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::TruncatedRecord { line: 2 }));
+
+        // half a word
+        assert!(matches!(load_bin(&[0x03], ByteOrder::LittleEndian), Err(ProgramLoadError::TruncatedRecord { .. })));
+    }
+
+    #[test]
+    pub fn test_invalid_record() {
+        let error = load_hex("0A000000030C3000040C1001030093").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::InvalidRecord { line: 1 }));
+        let error = load_hex(":00000001FZ").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::InvalidRecord { line: 1 }));
+    }
+
+    #[test]
+    pub fn test_io_error() {
+        let error = load_file("/nonexistent/program.hex").unwrap_err();
+        assert!(matches!(error, ProgramLoadError::Io(_)));
+        assert!(error.to_string().contains("No such file"));
+    }
+
+    #[test]
+    pub fn test_display() {
+        let error = ProgramLoadError::BadChecksum { line: 3, expected: 0x53, found: 0x54 };
+        assert_eq!(error.to_string(), "line 3: checksum is 0x54, expected 0x53");
+        assert_eq!(ProgramLoadError::AddressOutOfRange { address: 0x200 }.to_string(), "word address 0x200 is outside program memory");
+    }
+
+    #[test]
+    pub fn test_fuzz_seed_corpus() {
+        macro_rules! seed {
+            ($name:literal) => {
+                include_str!(concat!("../../fuzz/corpus/load_hex/", $name))
+            };
+        }
+        for valid in [seed!("valid.hex"), seed!("config_word.hex")] {
+            assert_eq!(load_hex(valid).unwrap()[..5], sample_program()[..5]);
+        }
+        let malformed = [
+            seed!("bad_checksum.hex"),
+            seed!("truncated.hex"),
+            seed!("missing_eof.hex"),
+            seed!("odd_address.hex"),
+            seed!("out_of_range.hex"),
+        ];
+        for seed in malformed {
+            assert!(load_hex(seed).is_err(), "{}", seed);
+        }
+    }
+}
@@ -74,8 +74,8 @@ mod test {
     #[test]
     pub fn test_nbit_12_and_usize() {
         let nbit12 = crate::nbitnumber::u12::new(0xFFF);
-        let res = nbit12.get() as usize & 0x1F as usize;
-        assert_eq!(res, 0x1F as usize);
+        let res = nbit12.get() as usize & 0x1F_usize;
+        assert_eq!(res, 0x1F_usize);
     }
 
     #[test] 
@@ -100,4 +100,52 @@ mod test {
         use crate::nbitnumber::NumberOperations;
         assert_eq!(crate::nbitnumber::u12::get_max().get(), 0xFFF);
     }
-}
\ No newline at end of file
+
+    #[test]
+    pub fn test_nbit_9_add_u16() {
+        let pc = crate::nbitnumber::u9::new(0x010);
+        assert_eq!(pc.add_u16(2).get(), 0x012);
+    }
+
+    #[test]
+    pub fn test_nbit_9_add_u16_wraps() {
+        let pc = crate::nbitnumber::u9::new(0x1FF);
+        assert_eq!(pc.add_u16(1).get(), 0x000);
+        assert_eq!(pc.add_u16(3).get(), 0x002);
+    }
+
+    #[test]
+    pub fn test_nbit_8_twos_complement() {
+        let value = crate::nbitnumber::NBitNumber::<8>::new(0x01);
+        assert_eq!(value.twos_complement().get(), 0xFF);
+        assert_eq!(crate::nbitnumber::NBitNumber::<8>::new(0x00).twos_complement().get(), 0x00);
+    }
+
+    #[test]
+    pub fn test_nbit_9_try_new() {
+        assert_eq!(crate::nbitnumber::u9::try_new(0x1FF), Ok(crate::nbitnumber::u9::new(0x1FF)));
+        // new masks the same value silently
+        assert_eq!(
+            crate::nbitnumber::u9::try_new(0x200),
+            Err(crate::nbitnumber::NBitError::TooWide { value: 0x200, bits: 9 })
+        );
+    }
+
+    #[test]
+    pub fn test_nbit_try_new_invalid_width() {
+        assert_eq!(
+            crate::nbitnumber::NBitNumber::<16>::try_new(0x0001),
+            Err(crate::nbitnumber::NBitError::InvalidWidth { bits: 16 })
+        );
+        assert_eq!(
+            crate::nbitnumber::NBitNumber::<0>::try_new(0x0000),
+            Err(crate::nbitnumber::NBitError::InvalidWidth { bits: 0 })
+        );
+    }
+
+    #[test]
+    pub fn test_nbit_8_zero() {
+        use crate::nbitnumber::{NBitNumber, NumberOperations};
+        assert_eq!(NBitNumber::<8>::zero(), NBitNumber::<8>::new(0));
+    }
+}
@@ -0,0 +1,157 @@
+#[cfg(test)]
+mod test {
+    use crate::nbitnumber::{u12, NBitNumber};
+    use crate::pic::{PICInstruction, PICInstructionMnemonic};
+
+    fn decode(word: u16) -> PICInstructionMnemonic {
+        PICInstruction::from_u12(u12::new(word)).decode_mnemonic()
+    }
+
+    #[test]
+    pub fn test_decode_miscellaneous() {
+        assert_eq!(decode(0x000), PICInstructionMnemonic::NOP);
+        assert_eq!(decode(0x002), PICInstructionMnemonic::OPTION);
+        assert_eq!(decode(0x003), PICInstructionMnemonic::SLEEP);
+        assert_eq!(decode(0x004), PICInstructionMnemonic::CLRWDT);
+        assert_eq!(decode(0x006), PICInstructionMnemonic::TRIS);
+        assert_eq!(decode(0x001), PICInstructionMnemonic::UND);
+    }
+
+    #[test]
+    pub fn test_align_full_width_opcodes() {
+        use crate::opcodes::{align_opcode, OPCODES};
+
+        let full_width = [
+            (PICInstructionMnemonic::NOP, 0x000),
+            (PICInstructionMnemonic::OPTION, 0x002),
+            (PICInstructionMnemonic::SLEEP, 0x003),
+            (PICInstructionMnemonic::CLRWDT, 0x004),
+        ];
+        for (mnemonic, word) in full_width {
+            let entry = OPCODES.iter().find(|entry| entry.mnemonic == mnemonic).unwrap();
+            assert_eq!(entry.opcode.bit_width(), 12, "{}", mnemonic);
+            assert_eq!(align_opcode(&entry.opcode), (word, 0xFFF), "{}", mnemonic);
+            assert_eq!(decode(word), mnemonic);
+            // every bit is fixed, so flipping any one of them is another instruction
+            for bit in 0..12 {
+                assert_ne!(decode(word ^ 1 << bit), mnemonic, "{} with bit {} flipped", mnemonic, bit);
+            }
+        }
+
+        // TRIS f and CLRW/CLRF keep operand bits below the fixed ones
+        let tris = OPCODES.iter().find(|entry| entry.mnemonic == PICInstructionMnemonic::TRIS).unwrap();
+        assert_eq!(align_opcode(&tris.opcode), (0x004, 0xFFC));
+        assert_eq!(decode(0x007), PICInstructionMnemonic::TRIS);
+        let clr = OPCODES.iter().find(|entry| entry.mnemonic == PICInstructionMnemonic::CLR).unwrap();
+        assert_eq!(align_opcode(&clr.opcode), (0x040, 0xFC0));
+    }
+
+    #[test]
+    pub fn test_decode_alu() {
+        assert_eq!(decode(0x030), PICInstructionMnemonic::MOVWF);
+        assert_eq!(decode(0x040), PICInstructionMnemonic::CLR);
+        assert_eq!(decode(0x1D0), PICInstructionMnemonic::ADDWF);
+        assert_eq!(decode(0x3FF), PICInstructionMnemonic::INCFSZ);
+    }
+
+    #[test]
+    pub fn test_decode_bit_control_literal() {
+        assert_eq!(decode(0x610), PICInstructionMnemonic::BTFSC);
+        assert_eq!(decode(0x8FF), PICInstructionMnemonic::RETLW);
+        assert_eq!(decode(0x910), PICInstructionMnemonic::CALL);
+        assert_eq!(decode(0xA00), PICInstructionMnemonic::GOTO);
+        assert_eq!(decode(0xBFF), PICInstructionMnemonic::GOTO);
+        assert_eq!(decode(0xC2A), PICInstructionMnemonic::MOVLW);
+    }
+
+    #[test]
+    pub fn test_nbit_width_and_value() {
+        use crate::nbitnumber::NBit;
+        let variants = [
+            NBit::U1(NBitNumber::new(0x1)),
+            NBit::U2(NBitNumber::new(0x3)),
+            NBit::U3(NBitNumber::new(0x7)),
+            NBit::U4(NBitNumber::new(0xF)),
+            NBit::U5(NBitNumber::new(0x1F)),
+            NBit::U6(NBitNumber::new(0x3F)),
+            NBit::U7(NBitNumber::new(0x7F)),
+            NBit::U8(NBitNumber::new(0xFF)),
+            NBit::U9(NBitNumber::new(0x1FF)),
+            NBit::U10(NBitNumber::new(0x3FF)),
+            NBit::U11(NBitNumber::new(0x7FF)),
+            NBit::U12(NBitNumber::new(0xFFF)),
+        ];
+        for (i, variant) in variants.iter().enumerate() {
+            let width = i + 1;
+            assert_eq!(variant.bit_width(), width);
+            assert_eq!(variant.as_u16(), (1 << width) - 1);
+        }
+    }
+
+    #[test]
+    pub fn test_nbit_saturating_into() {
+        use crate::nbitnumber::NBit;
+        let wide = NBit::U9(NBitNumber::new(0x1FF));
+        assert_eq!(wide.saturating_into::<5>().get(), 0x1F);
+        let narrow = NBit::U9(NBitNumber::new(0x00A));
+        assert_eq!(narrow.saturating_into::<5>().get(), 0x0A);
+    }
+
+    #[test]
+    pub fn test_mnemonic_name() {
+        assert_eq!(PICInstructionMnemonic::MOVLW.name(), "MOVLW");
+        assert_eq!(PICInstructionMnemonic::DECFSZ.to_string(), "DECFSZ");
+        let name: &'static str = PICInstructionMnemonic::GOTO.into();
+        assert_eq!(name, "GOTO");
+        assert_eq!(PICInstructionMnemonic::RETFIE.name(), "RETFIE");
+        assert_eq!(PICInstructionMnemonic::UND.name(), "UND");
+    }
+
+    #[test]
+    pub fn test_mnemonic_description() {
+        let add = PICInstructionMnemonic::ADDWF.description();
+        assert!(!add.is_empty());
+        assert_ne!(add, PICInstructionMnemonic::SUBWF.description());
+        assert_eq!(PICInstructionMnemonic::ADDWF.description(), "Add W to f");
+    }
+
+    #[test]
+    pub fn test_decode_table_matches_scan() {
+        use crate::nbitnumber::u9;
+        use crate::opcodes::{align_opcode, scan_opcodes, DECODE_TABLE, OPCODES};
+        use crate::pic::{PIC10F200, PipelinedTuringMachine};
+
+        // every mnemonic is overridden, only the one the table names marks W when executed
+        fn tabled(pic: &mut PIC10F200) {
+            pic.w_register = 0xA5;
+        }
+        fn other(pic: &mut PIC10F200) {
+            pic.w_register = 0x5A;
+        }
+        let mut pic = PIC10F200::new();
+        for mnemonic in OPCODES.iter().map(|entry| entry.mnemonic).chain([PICInstructionMnemonic::UND]) {
+            pic.set_executor(mnemonic, other);
+        }
+
+        for word in 0..0x1000u16 {
+            // the linear scan decode_mnemonic used before the table
+            let scanned = OPCODES
+                .iter()
+                .find(|entry| {
+                    let (opcode, mask) = align_opcode(&entry.opcode);
+                    word & mask == opcode
+                })
+                .map_or(PICInstructionMnemonic::UND, |entry| entry.mnemonic);
+            assert_eq!(PICInstruction::from_u12(u12::new(word)).decode_mnemonic(), scanned, "word 0x{:03X}", word);
+            assert_eq!(scan_opcodes(word), scanned);
+
+            let mnemonic = DECODE_TABLE[word as usize];
+            pic.set_executor(mnemonic, tabled);
+            pic.instruction_register = PICInstruction::from_u12(u12::new(word));
+            pic.instruction_address = Some(u9::new(0x000));
+            pic.execute();
+            assert_eq!(pic.w_register, 0xA5, "word 0x{:03X} did not execute as {:?}", word, mnemonic);
+            pic.set_executor(mnemonic, other);
+        }
+    }
+}
@@ -0,0 +1,331 @@
+#[cfg(test)]
+mod test {
+    use crate::data_memory::{GPIO_Masks, SpecialPurposeRegisters};
+    use crate::config::DeviceConfig;
+    use crate::nbitnumber::u12;
+    use crate::pic::{PIC10F200, PipelinedTuringMachine, Programmable};
+    use crate::tests::sample_program;
+
+    fn tmr0(pic: &PIC10F200) -> u8 {
+        pic.peek(SpecialPurposeRegisters::TMR0.address())
+    }
+
+    fn pulse_gp2(pic: &mut PIC10F200, pulses: usize) {
+        for _ in 0..pulses {
+            pic.set_input_pin(GPIO_Masks::GP2, true);
+            pic.set_input_pin(GPIO_Masks::GP2, false);
+        }
+    }
+
+    #[test]
+    pub fn test_timer0_counts_falling_edges() {
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        // TMR0 is undefined at power on
+        pic.poke(SpecialPurposeRegisters::TMR0.address(), 0x00);
+        // T0CS external, T0SE falling edge, prescaler assigned to the WDT
+        pic.option_register = 0b1111_1000;
+        pulse_gp2(&mut pic, 3);
+        assert_eq!(tmr0(&pic), 3);
+    }
+
+    #[test]
+    pub fn test_timer0_counts_rising_edges() {
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        // TMR0 is undefined at power on
+        pic.poke(SpecialPurposeRegisters::TMR0.address(), 0x00);
+        // T0CS external, T0SE rising edge, prescaler assigned to the WDT
+        pic.option_register = 0b1110_1000;
+        pic.set_input_pin(GPIO_Masks::GP2, true);
+        assert_eq!(tmr0(&pic), 1);
+        pic.set_input_pin(GPIO_Masks::GP2, false);
+        assert_eq!(tmr0(&pic), 1);
+    }
+
+    #[test]
+    pub fn test_timer0_external_prescaler() {
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        // TMR0 is undefined at power on
+        pic.poke(SpecialPurposeRegisters::TMR0.address(), 0x00);
+        // T0CS external, T0SE rising edge, prescaler 1:2 assigned to TMR0
+        pic.option_register = 0b1110_0000;
+        pulse_gp2(&mut pic, 5);
+        assert_eq!(tmr0(&pic), 2);
+    }
+
+    #[test]
+    pub fn test_timer0_ignores_pin_on_internal_clock() {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        // TMR0 is undefined at power on
+        pic.poke(SpecialPurposeRegisters::TMR0.address(), 0x00);
+        // T0CS internal, prescaler assigned to the WDT
+        pic.option_register = 0b1101_1000;
+        pulse_gp2(&mut pic, 3);
+        assert_eq!(tmr0(&pic), 0);
+
+        pic.run_cycles(4);
+        assert_eq!(tmr0(&pic), 4);
+    }
+
+    fn watchdog_pic(program: &[u16], period: u64) -> PIC10F200 {
+        let mut pic = PIC10F200::with_config(DeviceConfig::default().with_watchdog(period));
+        let mut words = sample_program();
+        for (address, word) in program.iter().enumerate() {
+            words[address] = u12::new(*word);
+        }
+        pic.program_chip(words);
+        pic.power_on_initialize();
+        pic
+    }
+
+    #[test]
+    pub fn test_clrwdt_clears_wdt_and_prescaler() {
+        // CLRWDT, GOTO 0x000
+        let mut pic = watchdog_pic(&[0x004, 0xA00], 100);
+        pic.run_cycles(2);
+        // PSA assigned to the WDT is the power on default
+        pic.watchdog = 50;
+        pic.prescaler = 5;
+        pic.run_cycles(1);
+        // cleared, then counts the CLRWDT cycle itself
+        assert_eq!(pic.watchdog, 1);
+        assert_eq!(pic.prescaler, 0);
+
+        // assigned to TMR0 the prescaler is left alone
+        let mut pic = watchdog_pic(&[0x004, 0xA00], 100);
+        pic.run_cycles(2);
+        pic.option_register = 0b1101_0111;
+        pic.prescaler = 5;
+        pic.run_cycles(1);
+        assert_eq!(pic.prescaler, 6);
+    }
+
+    #[test]
+    pub fn test_watchdog_timeout_resets() {
+        use crate::data_memory::Status_Masks;
+        use crate::nbitnumber::NBitNumber;
+
+        // BSF 0x10, 0 then spin on GOTO 0x001 without clearing the WDT
+        let mut pic = watchdog_pic(&[0x510, 0xA01], 10);
+        pic.option_register = 0b1101_1000; // WDT prescaler 1:1
+        pic.run_cycles(10);
+        assert_eq!(pic.program_counter.get(), 0xFF);
+        assert_eq!(pic.data_memory.get_flag(NBitNumber::new(Status_Masks::TO as u16)).get(), 0);
+        assert_eq!(pic.option_register, 0xFF);
+        // RAM survives the reset
+        assert_eq!(pic.peek(crate::nbitnumber::u5::new(0x10)), 0x01);
+    }
+
+    #[test]
+    pub fn test_comparator_change_wakes_from_sleep() {
+        use crate::data_memory::Status_Masks;
+        use crate::nbitnumber::NBitNumber;
+        use crate::pic::{HaltReason, PIC10F2Types};
+
+        // MOVLW 0xFE, MOVWF CMCON0 to enable wake on change, SLEEP
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F206));
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xCFE);
+        program[0x001] = u12::new(0x027);
+        program[0x002] = u12::new(0x003);
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.set_comparator_inputs(1.0, 2.0);
+
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Sleep);
+        assert!(!pic.comparator_output());
+
+        // CIN+ rises above CIN-
+        pic.set_comparator_inputs(3.0, 2.0);
+        assert!(pic.comparator_output());
+        assert_eq!(pic.halt_reason(), None);
+        // the reset goes back to the OSCCAL word at the top of the 512 words
+        assert_eq!(pic.program_counter.get(), 0x1FF);
+        let flag = |mask: Status_Masks| pic.data_memory.get_flag(NBitNumber::new(mask as u16)).get();
+        assert_eq!((flag(Status_Masks::CWUF), flag(Status_Masks::TO), flag(Status_Masks::PD)), (1, 1, 0));
+    }
+
+    #[test]
+    pub fn test_to_pd_through_clrwdt_sleep_and_wake() {
+        use crate::data_memory::Status_Masks;
+        use crate::nbitnumber::NBitNumber;
+        use crate::pic::{HaltReason, PIC10F2Types};
+
+        // CLRWDT, MOVLW 0xFE, MOVWF CMCON0, BTFSS STATUS, CWUF, SLEEP, GOTO 0x005
+        let mut program = sample_program();
+        for (address, word) in [0x004, 0xCFE, 0x027, 0x7C3, 0x003, 0xA05].into_iter().enumerate() {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F204).with_watchdog(2));
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.set_comparator_inputs(1.0, 2.0);
+        let flags = |pic: &PIC10F200| {
+            let flag = |mask: Status_Masks| pic.data_memory.get_flag(NBitNumber::new(mask as u16)).get();
+            (flag(Status_Masks::TO), flag(Status_Masks::PD))
+        };
+
+        // power up
+        assert_eq!(flags(&pic), (1, 1));
+        pic.run_cycles(3);
+        assert_eq!(flags(&pic), (1, 1), "CLRWDT");
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Sleep);
+        assert_eq!(flags(&pic), (1, 0), "SLEEP");
+
+        // a comparator change wakes the part through a reset
+        pic.set_comparator_inputs(3.0, 2.0);
+        assert_eq!(flags(&pic), (1, 0), "wake up");
+        pic.run_cycles(3);
+        assert_eq!(flags(&pic), (1, 1), "CLRWDT after waking");
+
+        // CWUF is set so the program spins instead of sleeping, until the WDT times out
+        let timed_out = (0..1_000).any(|_| {
+            pic.run_cycles(1);
+            flags(&pic).0 == 0
+        });
+        assert!(timed_out);
+        assert_eq!(flags(&pic), (0, 1), "WDT time-out");
+
+        // the time-out leaves PD as it was
+        let status = pic.peek(SpecialPurposeRegisters::STATUS.address());
+        pic.poke(SpecialPurposeRegisters::STATUS.address(), status & !(1 << Status_Masks::PD as u8));
+        pic.watchdog = 0;
+        pic.option_register = 0b1101_1000; // WDT prescaler 1:1
+        pic.run_cycles(2);
+        assert_eq!(flags(&pic), (0, 0), "WDT time-out with PD clear");
+    }
+
+    #[test]
+    pub fn test_gp2_follows_comparator_output() {
+        use crate::pic::PIC10F2Types;
+
+        // MOVLW 0xBF, MOVWF CMCON0 clears COUTEN, MOVLW 0x00, TRIS GPIO, MOVWF GPIO
+        let mut program = sample_program();
+        for (address, word) in [0xCBF, 0x027, 0xC00, 0x006, 0x026].into_iter().enumerate() {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F204));
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.set_comparator_inputs(3.0, 2.0);
+        assert!(!pic.comparator_output_on_gp2());
+
+        pic.run_cycles(4);
+        assert!(pic.comparator_output_on_gp2());
+        pic.run_cycles(3);
+        // the latch is low but the pin and GPIO reads follow COUT
+        assert_eq!(pic.gpio() & 0x04, 0x00);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), Some(true));
+        assert_eq!(pic.gpio_pins() & 0x04, 0x04);
+        assert!(!pic.pin_capabilities(GPIO_Masks::GP2).output);
+
+        pic.set_comparator_inputs(1.0, 2.0);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), Some(false));
+
+        // setting COUTEN gives GP2 back to the latch
+        pic.poke(SpecialPurposeRegisters::CMCON0.address(), 0xFF);
+        pic.set_comparator_inputs(3.0, 2.0);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), Some(false));
+    }
+
+    #[test]
+    pub fn test_reset_peripherals_keeps_ram() {
+        use crate::nbitnumber::u5;
+
+        let mut pic = PIC10F200::with_config(DeviceConfig::default().with_watchdog(100));
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        pic.option_register = 0b1100_0001; // TMR0 prescaler 1:4
+        pic.poke(u5::new(0x1F), 0x5A);
+        pic.run_cycles(10);
+        assert_ne!(tmr0(&pic), 0);
+        let pc = pic.program_counter;
+
+        pic.reset_peripherals();
+        assert_eq!((tmr0(&pic), pic.watchdog, pic.prescaler), (0, 0, 0));
+        assert_eq!(pic.peek(u5::new(0x1F)), 0x5A);
+        assert_eq!(pic.program_counter, pc);
+        assert_eq!(pic.option_register, 0b1100_0001);
+    }
+
+    #[test]
+    pub fn test_comparator_ignored_on_pic10f200() {
+        use crate::pic::HaltReason;
+
+        // the PIC10F200 has no comparator
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xCFE);
+        program[0x001] = u12::new(0x027);
+        program[0x002] = u12::new(0x003);
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.run_to_halt();
+        pic.set_comparator_inputs(3.0, 2.0);
+        assert_eq!(pic.halt_reason(), Some(HaltReason::Sleep));
+    }
+
+    #[test]
+    pub fn test_option_fields() {
+        let mut pic = PIC10F200::new();
+        // MOVLW 0b1010_1101, OPTION: GPWU set, GPPU clear, T0CS set, T0SE clear, PSA set, PS 5
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xCAD);
+        program[0x001] = u12::new(0x002);
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        pic.run_cycles(4);
+
+        assert_eq!(pic.option_register(), 0b1010_1101);
+        assert!(pic.option_gpwu() && !pic.option_gppu());
+        assert!(pic.option_t0cs() && !pic.option_t0se());
+        assert!(pic.option_psa());
+        assert_eq!(pic.option_prescale().get(), 5);
+    }
+
+    #[test]
+    pub fn test_wdt_wakes_from_sleep_after_its_period() {
+        use crate::data_memory::Status_Masks;
+        use crate::pic::HaltReason;
+
+        // MOVLW 0xF8, OPTION assigns the prescaler to the WDT at 1:1, SLEEP
+        let mut program = sample_program();
+        for (address, word) in [0xCF8, 0x002, 0x003].into_iter().enumerate() {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::with_config(DeviceConfig::default().with_watchdog(100));
+        pic.program_chip(program);
+        pic.power_on_initialize();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Sleep);
+        let slept_at = pic.cycles();
+
+        // the clock keeps counting while asleep but nothing executes, the period is counted
+        // from the SLEEP's own cycle, as it is from a CLRWDT's
+        pic.run_cycles(98);
+        assert_eq!(pic.halt_reason(), Some(HaltReason::Sleep));
+        assert_eq!(pic.cycles(), slept_at + 98);
+        assert_eq!(pic.w_register(), 0xF8);
+
+        pic.run_cycles(1);
+        assert_eq!(pic.halt_reason(), None);
+        assert_eq!(pic.program_counter.get(), 0x0FF);
+        assert!(!pic.data_memory.status(Status_Masks::TO) && !pic.data_memory.status(Status_Masks::PD));
+    }
+
+    #[test]
+    pub fn test_sleep_without_wdt_stops_the_clock() {
+        use crate::pic::HaltReason;
+
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        let status = pic.run_to_halt();
+        pic.run_cycles(1_000);
+        assert_eq!(pic.halt_reason(), Some(HaltReason::Sleep));
+        assert_eq!(pic.cycles(), status.cycles);
+    }
+}
@@ -1,32 +1,621 @@
 #[cfg(test)]
 mod test {
     // Import the module we want to test
-    use crate::pic::{self, PIC10F200};
-    use crate::data_memory::RegisterFile;
-    use crate::program_memory::ProgramMemory;
-    use crate::nbitnumber::u9;
-    use crate::pic::PICInstruction;
-    
+    use crate::pic::{BitIndex, Cpu, DecodedFields, FileReg, Literal, Operands, PinError, StateDiff, ExitStatus, HaltReason, PICInstruction, PICInstructionMnemonic, PICInstructionType, PIC10F200, Programmable, PipelinedTuringMachine};
+    use crate::data_memory::{GPIO_Masks, RegisterDiff, SpecialPurposeRegisters};
+    use crate::nbitnumber::{u12, u3, u5, u9, NBitNumber};
+    use crate::tests::sample_program;
+
+    // program the PIC with a program
+    fn program_chip(pic: &mut PIC10F200) {
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+    }
+
     #[test]
     fn test_pic10f200() {
-        let pic = PIC10F200 {
-            data_memory: RegisterFile::new(),
-            program_memory: ProgramMemory::new(),
-            program_counter: u9::new(0),
-            instruction_register: PICInstruction::new(),
-            w_register,
-            io_pins: todo!(),
-        };
+        let mut pic = PIC10F200::new();
+        program_chip(&mut pic);
+        assert_eq!(pic.cycles(), 0);
+        assert_eq!(pic.halt_reason(), None);
+    }
+
+    #[test]
+    fn test_run_to_halt_exit_w() {
+        let mut pic = PIC10F200::new();
+        program_chip(&mut pic);
+
+        let status = pic.run_to_halt();
+        // 3 | 4, left in W before the SLEEP
+        assert_eq!(status, ExitStatus { cycles: 7, w: 0x07, output: None, halt_reason: HaltReason::Sleep });
+        assert_eq!(pic.halt_reason(), Some(HaltReason::Sleep));
+    }
 
-        let program = [0x000; 0x200];
+    #[test]
+    fn test_run_to_halt_output_register() {
+        // MOVLW 0x2A, MOVWF 0x10, CLRW, SLEEP
+        let mut program = [u12::new(0); 0x200];
+        for (address, word) in [0xC2A, 0x030, 0x040, 0x003].into_iter().enumerate() {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::new();
         pic.program_chip(program);
-        
         pic.power_on_initialize();
+        pic.set_output_register(Some(u5::new(0x10)));
+
+        let status = pic.run_to_halt();
+        assert_eq!(status.w, 0x00);
+        assert_eq!(status.output, Some(0x2A));
     }
 
-    // program the PIC with a program
-    fn test_program_chip(pic: &mut PIC10F200) {
+    #[test]
+    fn test_reset_on_halt() {
+        let mut pic = PIC10F200::new();
+        program_chip(&mut pic);
+        pic.set_reset_on_halt(true);
+
+        let first = pic.run_to_halt();
+        assert_eq!(pic.halt_reason(), None);
+        assert_eq!(pic.cycles(), 0);
+
+        // the machine is ready to run the same program again
+        let second = pic.run_to_halt();
+        assert_eq!(first, second);
+    }
+
+    #[test]
+    fn test_undefined_instruction_halts() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        // 0x001 is not a defined miscellaneous instruction
+        program[0x000] = u12::new(0x001);
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::UndefinedInstruction);
+    }
+
+    #[test]
+    fn test_gpio_register_path() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xC05); // MOVLW 0x05
+        program[0x001] = u12::new(0x020 | SpecialPurposeRegisters::GPIO as u16); // MOVWF GPIO
+        program[0x002] = u12::new(0x003); // SLEEP
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        pic.run_to_halt();
+        assert_eq!(pic.gpio(), 0x05);
+    }
+
+    #[test]
+    fn test_run_and_diff_addwf() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xC0F); // MOVLW 0x0F
+        program[0x001] = u12::new(0x030); // MOVWF 0x10
+        program[0x002] = u12::new(0xC01); // MOVLW 0x01
+        program[0x003] = u12::new(0x1F0); // ADDWF 0x10, F
+        program[0x004] = u12::new(0x003); // SLEEP
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        // fill the pipeline and run up to the ADDWF
+        pic.run_cycles(5);
+        let diff = pic.run_and_diff(1);
+
+        assert_eq!(diff, vec![
+            RegisterDiff { address: SpecialPurposeRegisters::STATUS.address(), old: 0x18, new: 0x1A },
+            RegisterDiff { address: u5::new(0x10), old: 0x0F, new: 0x10 },
+        ]);
+    }
+
+    #[test]
+    fn test_poke_before_running() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xC05); // MOVLW 0x05
+        program[0x001] = u12::new(0x1F0); // ADDWF 0x10, F
+        program[0x002] = u12::new(0x003); // SLEEP
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        pic.poke(u5::new(0x10), 0x20);
+        pic.run_to_halt();
+        assert_eq!(pic.peek(u5::new(0x10)), 0x25);
+    }
+
+    #[test]
+    fn test_poke_through_indf() {
+        let mut pic = PIC10F200::new();
+        pic.poke(SpecialPurposeRegisters::FSR.address(), 0x12);
+        pic.poke(u5::new(0x12), 0x34);
+        assert_eq!(pic.peek(SpecialPurposeRegisters::INDF.address()), 0x34);
+    }
+
+    #[test]
+    fn test_pipeline_state_execute_follows_fetch() {
+        let mut pic = PIC10F200::new();
+        program_chip(&mut pic);
+
+        // the pipeline starts empty, with the OSCCAL slot at 0xFF up for fetch
+        let state = pic.pipeline_state();
+        assert_eq!(state.execute_address, None);
+        assert_eq!(state.fetch_address, u9::new(0xFF));
+
+        pic.tick();
+        let next = pic.pipeline_state();
+        assert_eq!(next.execute_address, Some(state.fetch_address));
+        assert_eq!(next.execute.instruction_raw, state.fetch.instruction_raw);
+        assert_eq!(next.fetch_address, u9::new(0x000));
+        assert_eq!(next.fetch.instruction_raw, u12::new(0xC03));
+    }
+
+    #[test]
+    fn test_floating_input_pull_ups() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0xC9F); // MOVLW 0x9F, GPPU clear enables the weak pull-ups
+        program[0x001] = u12::new(0x002); // OPTION
+        program[0x002] = u12::new(0x206); // MOVF GPIO, W
+        program[0x003] = u12::new(0x003); // SLEEP
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        // pull-ups are disabled from power on, so a floating pin is undefined
+        assert_eq!(pic.pin_level(GPIO_Masks::GP0), None);
+
+        // GP0 floats, GP1 is held low, GP2 has no pull-up, GP3 floats
+        pic.set_input_pin(GPIO_Masks::GP1, false);
+        pic.run_to_halt();
+        assert_eq!(pic.pin_level(GPIO_Masks::GP0), Some(true));
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), None);
+        assert_eq!(pic.w_register(), 0b1001);
+
+        // once driven the pin follows the input instead
+        pic.set_input_pin(GPIO_Masks::GP0, false);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP0), Some(false));
+        pic.float_input_pin(GPIO_Masks::GP0);
+        assert_eq!(pic.input_pin(GPIO_Masks::GP0), None);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP0), Some(true));
+    }
+
+    #[test]
+    fn test_stimulus_drives_input_during_run() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0x606); // loop: BTFSC GPIO, 0
+        program[0x001] = u12::new(0xA03); // GOTO pressed
+        program[0x002] = u12::new(0xA00); // GOTO loop
+        program[0x003] = u12::new(0xC42); // pressed: MOVLW 0x42
+        program[0x004] = u12::new(0x003); // SLEEP
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        pic.apply_stimulus(&[(100, GPIO_Masks::GP0, true), (1, GPIO_Masks::GP0, false)]);
+        pic.run_cycles(100);
+        assert_eq!(pic.input_pin(GPIO_Masks::GP0), Some(true));
+        assert_eq!(pic.w_register(), 0x00);
+        assert_eq!(pic.halt_reason(), None);
+
+        // the loop sees the press within a pass of the loop
+        let status = pic.run_to_halt();
+        assert_eq!(status.w, 0x42);
+        assert!(status.cycles <= 110);
+    }
 
-        
+    #[test]
+    fn test_gpio_reads_sample_pins_at_read_time() {
+        let mut pic = PIC10F200::new();
+        let mut program = sample_program();
+        program[0x000] = u12::new(0x206); // MOVF GPIO, W
+        program[0x001] = u12::new(0x030); // MOVWF 0x10
+        program[0x002] = u12::new(0x206); // MOVF GPIO, W
+        program[0x003] = u12::new(0x031); // MOVWF 0x11
+        program[0x004] = u12::new(0x003); // SLEEP
+        pic.program_chip(program);
+        pic.power_on_initialize();
+
+        // the reads execute on cycles 3 and 5, GP3 falls at the start of cycle 5
+        pic.apply_stimulus(&[(0, GPIO_Masks::GP3, true), (5, GPIO_Masks::GP3, false)]);
+        pic.run_to_halt();
+        assert_eq!(pic.peek(u5::new(0x10)) & 0x08, 0x08);
+        assert_eq!(pic.peek(u5::new(0x11)) & 0x08, 0x00);
+    }
+
+    #[test]
+    pub fn test_tooling_through_cpu_trait() {
+        // MOVLW 0x2A, MOVWF 0x10, SLEEP as little endian words
+        let path = std::env::temp_dir().join(format!("pic10_cpu_trait_{}.bin", std::process::id()));
+        std::fs::write(&path, [0x2A, 0x0C, 0x30, 0x00, 0x03, 0x00]).unwrap();
+
+        let mut cpu: Box<dyn Cpu> = Box::new(PIC10F200::new());
+        crate::loader::program_from_file(cpu.as_mut(), &path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        cpu.reset();
+        assert_eq!(cpu.pc(), u9::new(0xFF));
+        assert_eq!(crate::disassembler::disassemble_at(cpu.as_ref(), u9::new(0x000), None), "MOVLW 0x2A");
+
+        for _ in 0..4 {
+            cpu.step();
+        }
+        assert_eq!(cpu.peek(u5::new(0x10)), 0x2A);
+        cpu.poke(u5::new(0x11), 0x5A);
+        assert_eq!(cpu.peek(u5::new(0x11)), 0x5A);
+        assert_eq!(cpu.program_word(u9::new(0x002)), u12::new(0x003));
+    }
+
+    #[test]
+    pub fn test_trap_on_unflashed_execution() {
+        let mut pic = PIC10F200::new();
+        // MOVLW 0x2A, GOTO 0x040 into blank memory
+        pic.program_words(u9::new(0x000), &[u12::new(0xC2A), u12::new(0xA40)]);
+        pic.set_trap_unflashed(true);
+        pic.power_on_initialize();
+
+        let status = pic.run_to_halt();
+        assert_eq!(status.halt_reason, HaltReason::UnflashedExecution(u9::new(0x040)));
+        assert_eq!(status.w, 0x2A);
+    }
+
+    #[test]
+    pub fn test_trap_on_uninitialized_w() {
+        let mut pic = PIC10F200::new();
+        // MOVWF 0x10 with nothing loaded into W, the OSCCAL word is erased
+        pic.program_words(u9::new(0x000), &[u12::new(0x030), u12::new(0x003)]);
+        pic.set_trap_uninitialized_w(true);
+        pic.power_on_initialize();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::UninitializedW(u9::new(0x000)));
+
+        // the sample program loads W with MOVLW before using it
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Sleep);
+
+        // CLRW writes W, CLRF does not
+        pic.program_words(u9::new(0x000), &[u12::new(0x070), u12::new(0x040), u12::new(0x030), u12::new(0x003)]);
+        pic.power_on_initialize();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::Sleep);
+        pic.program_words(u9::new(0x000), &[u12::new(0x070), u12::new(0x030), u12::new(0x003)]);
+        pic.power_on_initialize();
+        assert_eq!(pic.run_to_halt().halt_reason, HaltReason::UninitializedW(u9::new(0x001)));
+    }
+
+    #[test]
+    pub fn test_unflashed_words_run_as_nop_without_trap() {
+        let mut pic = PIC10F200::new();
+        pic.program_words(u9::new(0x000), &[u12::new(0xC2A), u12::new(0xA40)]);
+        pic.power_on_initialize();
+        pic.run_cycles(10);
+        assert_eq!(pic.halt_reason(), None);
+        assert_eq!(pic.program_counter, u9::new(0x046));
+    }
+
+    #[test]
+    pub fn test_decoded_bit_operation() {
+        // BTFSC 0x03, 2
+        let fields = PICInstruction::from_u12(u12::new(0x643)).decoded();
+        assert_eq!(fields, DecodedFields {
+            category: PICInstructionType::BitOperation,
+            mnemonic: PICInstructionMnemonic::BTFSC,
+            f: Some(FileReg(u5::new(0x03))),
+            d: None,
+            k: None,
+            b: Some(BitIndex(u3::new(2))),
+            goto_target: None,
+        });
+    }
+
+    #[test]
+    pub fn test_decoded_other_forms() {
+        // GOTO 0x1A5
+        let fields = PICInstruction::from_u12(u12::new(0xBA5)).decoded();
+        assert_eq!((fields.goto_target, fields.k, fields.f), (Some(u9::new(0x1A5)), None, None));
+
+        // ADDWF 0x10, F
+        let fields = PICInstruction::from_u12(u12::new(0x1F0)).decoded();
+        assert_eq!((fields.f, fields.d, fields.b), (Some(FileReg(u5::new(0x10))), Some(NBitNumber::new(1)), None));
+
+        // CLRW
+        let fields = PICInstruction::from_u12(u12::new(0x040)).decoded();
+        assert_eq!((fields.f, fields.d), (None, Some(NBitNumber::new(0))));
+    }
+
+    #[test]
+    pub fn test_encode_typed_operands() {
+        use PICInstructionMnemonic::*;
+
+        let word = |mnemonic, operands| PICInstruction::encode(mnemonic, operands).map(|instruction| instruction.instruction_raw.get());
+        assert_eq!(word(MOVLW, Operands::Literal(Literal(0x2A))), Some(0xC2A));
+        assert_eq!(word(ADDWF, Operands::FileDestination(FileReg(u5::new(0x10)), NBitNumber::new(1))), Some(0x1F0));
+        assert_eq!(word(BSF, Operands::FileBit(FileReg(u5::new(0x03)), BitIndex(u3::new(5)))), Some(0x5A3));
+        assert_eq!(word(GOTO, Operands::Target(u9::new(0x1A5))), Some(0xBA5));
+        assert_eq!(word(CLR, Operands::None), Some(0x040));
+        assert_eq!(word(CLR, Operands::File(FileReg(u5::new(0x10)))), Some(0x070));
+        assert_eq!(word(TRIS, Operands::File(FileReg(u5::new(0x06)))), Some(0x006));
+        assert_eq!(word(SLEEP, Operands::None), Some(0x003));
+        // as in the assembler a byte operation without a destination stores to the register
+        assert_eq!(word(ADDWF, Operands::File(FileReg(u5::new(0x10)))), Some(0x1F0));
+
+        // a literal where the form wants a register, or no operands, is refused
+        // (Operands::File(Literal(0x10)) does not compile)
+        assert_eq!(word(MOVWF, Operands::Literal(Literal(0x10))), None);
+        assert_eq!(word(ADDWF, Operands::Literal(Literal(0x10))), None);
+        assert_eq!(word(TRIS, Operands::File(FileReg(u5::new(0x04)))), None);
+        assert_eq!(word(RETURN, Operands::None), None);
+
+        // decoded gives back the same operands
+        let fields = PICInstruction::encode(BTFSS, Operands::FileBit(FileReg(u5::new(0x11)), BitIndex(u3::new(7)))).unwrap().decoded();
+        assert_eq!((fields.f, fields.b), (Some(FileReg(u5::new(0x11))), Some(BitIndex(u3::new(7)))));
+    }
+
+    #[test]
+    pub fn test_output_to_input_only_pin_rejected() {
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        assert_eq!(pic.set_output_pin(3, true), Err(PinError::InputOnly(GPIO_Masks::GP3)));
+        assert_eq!(pic.set_output_pin(4, true), Err(PinError::NoSuchPin(4)));
+        assert_eq!(pic.drive_pin(7, true), Err(PinError::NoSuchPin(7)));
+        assert!(!pic.pin_capabilities(GPIO_Masks::GP3).output);
+
+        // GP2 is T0CKI while T0CS is set, as it is after power on
+        assert_eq!(pic.set_output_pin(2, true), Err(PinError::InUseByPeripheral(GPIO_Masks::GP2)));
+        pic.option_register = 0xDF;
+        assert_eq!(pic.set_output_pin(2, true), Ok(()));
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), Some(true));
+        assert_eq!(pic.tris_register & 0x04, 0x00);
+    }
+
+    #[test]
+    pub fn test_pc_wraps_at_top_of_device_memory() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        for (device, top) in [(PIC10F2Types::PIC10F200, 0x0FF), (PIC10F2Types::PIC10F206, 0x1FF)] {
+            // memory erased to NOPs, so the PC just counts
+            let mut pic = PIC10F200::with_config(DeviceConfig::new(device));
+            pic.program_chip([u12::new(0); 0x200]);
+            pic.power_on_initialize();
+            assert_eq!(pic.program_counter, u9::new(top), "{:?}", device);
+
+            // the OSCCAL word is fetched from the top, then the PC wraps to the reset vector
+            pic.run_cycles(1);
+            assert_eq!(pic.program_counter, u9::new(0x000), "{:?}", device);
+
+            pic.set_pc(u9::new(top - 1)).unwrap();
+            pic.run_cycles(1);
+            assert_eq!(pic.instruction_address, Some(u9::new(top)), "{:?}", device);
+            assert_eq!(pic.program_counter, u9::new(0x000), "{:?}", device);
+        }
+    }
+
+    #[test]
+    pub fn test_clock_out_on_gp2() {
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        pic.option_register = 0xDF;
+        pic.set_output_pin(2, true).unwrap();
+        assert_eq!(pic.clock_out_level(0), None);
+
+        // the OSCCAL value is loaded with FOSC4 clear, setting it takes GP2 over
+        pic.poke(SpecialPurposeRegisters::OSCCAL.address(), 0xFF);
+        assert_eq!(pic.set_output_pin(2, true), Err(PinError::InUseByPeripheral(GPIO_Masks::GP2)));
+
+        // two edges every instruction cycle, whatever the latch holds
+        let levels: Vec<bool> = (0..4 * 3).map(|phase| pic.clock_out_level(phase).unwrap()).collect();
+        let edges = levels.windows(2).filter(|pair| pair[0] != pair[1]).count();
+        assert_eq!(edges, 2 * 3 - 1);
+        assert_eq!(&levels[..4], [false, false, true, true]);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), Some(false));
+
+        pic.poke(SpecialPurposeRegisters::OSCCAL.address(), 0xFE);
+        assert_eq!(pic.pin_level(GPIO_Masks::GP2), Some(true));
+    }
+
+    #[test]
+    pub fn test_comparator_claims_gp0_gp1() {
+        let mut pic = PIC10F200::with_config(crate::config::DeviceConfig::new(crate::pic::PIC10F2Types::PIC10F204));
+        pic.power_on_initialize();
+        // CMCON0 powers on with CMPON set
+        assert_eq!(pic.set_output_pin(0, false), Err(PinError::InUseByPeripheral(GPIO_Masks::GP0)));
+
+        // the PIC10F200 has no comparator to take the pin
+        let mut pic = PIC10F200::new();
+        pic.power_on_initialize();
+        assert_eq!(pic.set_output_pin(0, false), Ok(()));
+        assert_eq!(pic.pin_level(GPIO_Masks::GP0), Some(false));
+    }
+
+    #[test]
+    pub fn test_reset_vector_fetched_after_osccal() {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        assert_eq!(pic.reset_vector(), u9::new(0x000));
+
+        // the first tick fetches the OSCCAL word, the second executes it and fetches the reset vector
+        pic.run_cycles(2);
+        assert_eq!(pic.pipeline_state().execute_address, Some(pic.reset_vector()));
+
+        // which runs on the next tick, MOVLW 0x03
+        pic.run_cycles(1);
+        assert_eq!(pic.w_register(), 0x03);
+
+        // a watchdog or MCLR style reset comes back the same way
+        crate::peripherals::device_reset(&mut pic);
+        pic.run_cycles(2);
+        assert_eq!(pic.pipeline_state().execute_address, Some(pic.reset_vector()));
+    }
+
+    #[test]
+    pub fn test_fast_run_matches_tick() {
+        // counts 0x11 up ten times then sleeps, TMR0 stays on T0CKI and nothing else is enabled
+        let mut program = sample_program();
+        let listing = [0xC0A, 0x030, 0x2B1, 0x2F0, 0xA02, 0x211, 0x003];
+        for (address, word) in listing.iter().enumerate() {
+            program[address] = u12::new(*word);
+        }
+        let mut fast = PIC10F200::new();
+        fast.program_chip(program);
+        fast.power_on_initialize();
+        let mut slow = PIC10F200::new();
+        slow.program_chip(program);
+        slow.power_on_initialize();
+
+        fast.run_cycles(100);
+        for _ in 0..100 {
+            slow.tick();
+        }
+        assert_eq!(fast.halt_reason(), Some(HaltReason::Sleep));
+        assert_eq!((fast.cycles(), fast.w_register(), fast.program_counter), (slow.cycles(), slow.w_register(), slow.program_counter));
+        assert!(fast.data_memory.diff(&slow.data_memory).is_empty());
+        assert_eq!(fast.peek(u5::new(0x11)), 0x0A);
+    }
+
+    #[test]
+    pub fn test_state_diff() {
+        let mut a = PIC10F200::new();
+        a.program_chip(sample_program());
+        a.power_on_initialize();
+        a.run_cycles(3);
+        let mut b = PIC10F200::new();
+        b.program_chip(sample_program());
+        b.power_on_initialize();
+        b.run_cycles(3);
+        assert!(a.diff(&b).is_empty());
+
+        b.w_register = 0x55;
+        b.poke(u5::new(0x12), 0x01);
+        b.poke(SpecialPurposeRegisters::STATUS.address(), 0x19);
+        b.program_memory.push(u9::new(0x023));
+        assert_eq!(a.diff(&b), StateDiff {
+            registers: vec![
+                RegisterDiff { address: SpecialPurposeRegisters::STATUS.address(), old: 0x18, new: 0x19 },
+                RegisterDiff { address: u5::new(0x12), old: 0x00, new: 0x01 },
+            ],
+            w: Some((0x03, 0x55)),
+            pc: None,
+            flags: 0x01,
+            stack: Some(([u9::new(0); 2], [u9::new(0x023), u9::new(0)])),
+        });
+    }
+
+    #[test]
+    pub fn test_program_chip_then_power_on() {
+        use crate::config::DeviceConfig;
+
+        let config = DeviceConfig::new(crate::pic::PIC10F2Types::PIC10F204).with_watchdog(50_000);
+        let mut pic = PIC10F200::with_config(config);
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        pic.run_to_halt();
+        pic.poke(u5::new(0x10), 0x99);
+
+        let mut program = sample_program();
+        program[0x0FF] = u12::new(0xC24); // MOVLW 0x24, the calibration value
+        pic.program_chip(program);
+        assert_eq!(pic.peek(u5::new(0x10)), 0x00);
+        assert_eq!(*pic.config(), config);
+
+        // power on puts the special purpose registers back and runs the OSCCAL word first
+        pic.power_on_initialize();
+        assert_eq!(pic.peek(SpecialPurposeRegisters::OSCCAL.address()), 0xFE);
+        assert_eq!(pic.peek(SpecialPurposeRegisters::STATUS.address()), 0x18);
+        pic.run_cycles(2);
+        assert_eq!(pic.w_register(), 0x24);
+    }
+
+    #[test]
+    pub fn test_execute_events_are_logged() {
+        use std::sync::Mutex;
+
+        static MESSAGES: Mutex<Vec<String>> = Mutex::new(Vec::new());
+        struct Capture;
+        impl log::Log for Capture {
+            fn enabled(&self, _: &log::Metadata) -> bool {
+                true
+            }
+            fn log(&self, record: &log::Record) {
+                MESSAGES.lock().unwrap().push(record.args().to_string());
+            }
+            fn flush(&self) {}
+        }
+        static CAPTURE: Capture = Capture;
+        log::set_logger(&CAPTURE).unwrap();
+        log::set_max_level(log::LevelFilter::Trace);
+
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        pic.run_to_halt();
+        log::set_max_level(log::LevelFilter::Off);
+
+        // other tests may log while the level is up, so only look for this program's events
+        let messages = MESSAGES.lock().unwrap();
+        assert!(messages.iter().any(|message| message == "execute 0x000: MOVLW"));
+        assert!(messages.iter().any(|message| message.starts_with("halt at cycle") && message.ends_with("Sleep")));
+    }
+
+    #[test]
+    pub fn test_extractors_against_hand_decoded_fields() {
+        let extract = |word: u16| {
+            let instruction = PICInstruction::from_u12(u12::new(word));
+            (
+                instruction.extract_f().get(),
+                instruction.extract_d().get(),
+                instruction.extract_b().get(),
+                instruction.extract_k(),
+                instruction.extract_k_goto().get(),
+                instruction.extract_f_tris().get(),
+            )
+        };
+        // ADDWF 0x1F, F: 0001 11 1 11111
+        assert_eq!(extract(0x1FF), (0x1F, 1, 0x7, 0xFF, 0x1FF, 0x3));
+        // MOVF 0x10, W: 0010 00 0 10000
+        assert_eq!(extract(0x210).0, 0x10);
+        assert_eq!(extract(0x210).1, 0);
+        // BSF 0x06, 5: 0101 101 00110
+        assert_eq!((extract(0x5A6).0, extract(0x5A6).2), (0x06, 5));
+        // BTFSS 0x03, 2: 0111 010 00011
+        assert_eq!((extract(0x743).0, extract(0x743).2), (0x03, 2));
+        // MOVLW 0xA5, RETLW 0x3C
+        assert_eq!(extract(0xCA5).3, 0xA5);
+        assert_eq!(extract(0x83C).3, 0x3C);
+        // GOTO 0x155 and CALL 0x0AA keep 9 and 8 bits
+        assert_eq!(extract(0xB55).4, 0x155);
+        assert_eq!(extract(0x9AA).3, 0xAA);
+        // TRIS GPIO
+        assert_eq!(extract(0x006).5, 0x2);
+    }
+
+    #[test]
+    pub fn test_nop_executor_override() {
+        fn counting_nop(pic: &mut PIC10F200) {
+            let count = pic.peek(u5::new(0x1F));
+            pic.poke(u5::new(0x1F), count + 1);
+        }
+
+        // three NOPs, GOTO 0x005 which flushes a fetch, then SLEEP
+        let mut program = sample_program();
+        for (address, word) in [0x000, 0x000, 0x000, 0xA05, 0x000, 0x003].into_iter().enumerate() {
+            program[address] = u12::new(word);
+        }
+        let mut pic = PIC10F200::new();
+        pic.program_chip(program);
+        pic.set_executor(PICInstructionMnemonic::NOP, counting_nop);
+        pic.power_on_initialize();
+        let status = pic.run_to_halt();
+
+        // the OSCCAL word and the three NOPs, not the flushed fetch, and timing is unchanged
+        assert_eq!(pic.peek(u5::new(0x1F)), 4);
+        assert_eq!(status.halt_reason, HaltReason::Sleep);
+        assert_eq!(status.cycles, 8);
+
+        pic.clear_executor(PICInstructionMnemonic::NOP);
+        pic.power_on_initialize();
+        pic.run_to_halt();
+        assert_eq!(pic.peek(u5::new(0x1F)), 4);
     }
-}
\ No newline at end of file
+}
@@ -0,0 +1,36 @@
+#[cfg(test)]
+mod test {
+    use crate::config::DeviceConfig;
+    use crate::pic::{PIC10F200, PIC10F2Types, PipelinedTuringMachine, Programmable};
+    use crate::power::PowerState;
+    use crate::tests::sample_program;
+
+    #[test]
+    pub fn test_sleep_fraction() {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        assert_eq!(pic.power_state(), PowerState::Active);
+
+        // the sample program executes SLEEP during cycle 7 and stays asleep
+        pic.run_for(100);
+        assert_eq!(pic.power_state(), PowerState::Sleep);
+        let report = pic.power_report();
+        assert_eq!((report.active, report.idle, report.sleep), (7, 0, 93));
+        assert_eq!(report.sleep_fraction(), 0.93);
+
+        pic.clear_power_report();
+        assert_eq!(pic.power_report().sleep_fraction(), 0.0);
+    }
+
+    #[test]
+    pub fn test_power_up_delay_is_idle() {
+        let mut pic = PIC10F200::with_config(DeviceConfig::new(PIC10F2Types::PIC10F200).with_power_up_delay(10));
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        assert_eq!(pic.power_state(), PowerState::Idle);
+        pic.run_for(10);
+        assert_eq!(pic.power_report().idle, 10);
+        assert_eq!(pic.power_state(), PowerState::Active);
+    }
+}
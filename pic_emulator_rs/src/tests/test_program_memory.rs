@@ -0,0 +1,159 @@
+#[cfg(test)]
+mod test {
+    use crate::nbitnumber::{u12, u9, NBitNumber};
+    use crate::program_memory::ProgramMemory;
+    use crate::tests::sample_program;
+
+    #[test]
+    pub fn test_flash_masks_upper_nibble() {
+        let mut program = [u12::new(0); 0x200];
+        // built without new() so the upper nibble is not masked off
+        program[0x000] = NBitNumber { value: 0xFC2A };
+        program[0x1FF] = NBitNumber { value: 0xF003 };
+
+        let mut program_memory = ProgramMemory::new();
+        program_memory.flash(program);
+
+        assert_eq!(program_memory.fetch(u9::new(0x000)).get(), 0xC2A);
+        assert_eq!(program_memory.fetch(u9::new(0x1FF)).get(), 0x003);
+    }
+
+    #[test]
+    pub fn test_flash_preserves_12_bits() {
+        let mut program = [u12::new(0); 0x200];
+        program[0x010] = u12::new(0xFFF);
+
+        let mut program_memory = ProgramMemory::new();
+        program_memory.flash(program);
+
+        assert_eq!(program_memory.fetch(u9::new(0x010)).get(), 0xFFF);
+    }
+
+    #[test]
+    pub fn test_from_array_and_iter() {
+        let program_memory = ProgramMemory::from(sample_program());
+
+        let words: Vec<u12> = program_memory.iter().copied().collect();
+        assert_eq!(words.len(), 0x200);
+        assert_eq!(words[..5], sample_program()[..5]);
+        assert!(program_memory.iter().skip(5).all(|word| *word == u12::new(0)));
+    }
+
+    #[test]
+    pub fn test_flash_words_marks_only_written_words() {
+        let mut program_memory = ProgramMemory::from(sample_program());
+        assert!(program_memory.is_flashed(u9::new(0x0FF)));
+
+        program_memory.flash_words(u9::new(0x010), &[u12::new(0xC01), u12::new(0x003)]);
+        assert_eq!(program_memory.fetch(u9::new(0x011)), u12::new(0x003));
+        assert!(program_memory.is_flashed(u9::new(0x010)) && program_memory.is_flashed(u9::new(0x011)));
+        // everything else was erased
+        assert_eq!(program_memory.fetch(u9::new(0x000)), u12::new(0x000));
+        assert!(!program_memory.is_flashed(u9::new(0x000)) && !program_memory.is_flashed(u9::new(0x012)));
+    }
+
+    #[test]
+    pub fn test_used_and_free_words() {
+        let mut program_memory = ProgramMemory::from(sample_program());
+        // the five instructions of the sample program, out of the 255 below OSCCAL
+        assert_eq!(program_memory.used_words(), 5);
+        assert_eq!(program_memory.free_words(), 250);
+
+        // a NOP written on purpose takes a word
+        program_memory.flash_words(u9::new(0x000), &[u12::new(0xC01), u12::new(0x000), u12::new(0x003)]);
+        assert_eq!((program_memory.used_words(), program_memory.free_words()), (3, 252));
+    }
+
+    #[test]
+    pub fn test_disassemble_at() {
+        let mut program = sample_program();
+        program[0x0FF] = u12::new(0xCFE); // MOVLW 0xFE
+        let program_memory = ProgramMemory::from(program);
+        assert_eq!(program_memory.disassemble_at(u9::new(0x001)), "MOVWF 0x10");
+        assert_eq!(program_memory.disassemble_at(u9::new(0x0FF)), "MOVLW 0xFE ; OSCCAL");
+
+        // on a 512 word part the OSCCAL word moves to the top
+        let mut program_memory = ProgramMemory::with_words(0x200);
+        program_memory.flash(program);
+        assert_eq!(program_memory.disassemble_at(u9::new(0x0FF)), "MOVLW 0xFE");
+        assert_eq!(program_memory.disassemble_at(u9::new(0x1FF)), "NOP ; OSCCAL");
+    }
+
+    #[test]
+    pub fn test_decoded_iter_finds_goto_targets() {
+        use crate::pic::PICInstructionMnemonic;
+
+        let mut program = [u12::new(0); 0x200];
+        program[0x000] = u12::new(0xA05); // GOTO 0x005
+        program[0x003] = u12::new(0xC01); // MOVLW 0x01
+        program[0x005] = u12::new(0xA03); // GOTO 0x003
+        let mut program_memory = ProgramMemory::new();
+        program_memory.flash(program);
+
+        let targets: Vec<(u16, u16)> = program_memory
+            .decoded_iter()
+            .filter(|(_, instruction)| instruction.decode_mnemonic() == PICInstructionMnemonic::GOTO)
+            .map(|(address, instruction)| (address.get(), instruction.extract_k_goto().get()))
+            .collect();
+        assert_eq!(targets, vec![(0x000, 0x005), (0x005, 0x003)]);
+        // only the words the device implements
+        assert_eq!(program_memory.decoded_iter().count(), 0x100);
+    }
+
+    #[test]
+    pub fn test_write_listing() {
+        let mut program_memory = ProgramMemory::new();
+        program_memory.flash(sample_program());
+        let mut listing = Vec::new();
+        program_memory.write_listing(&mut listing).unwrap();
+        let listing = String::from_utf8(listing).unwrap();
+        let lines: Vec<&str> = listing.lines().collect();
+
+        assert_eq!(lines[0], "000  C03  MOVLW 0x03");
+        assert_eq!(lines[4], "004  003  SLEEP");
+        // the erased words up to the OSCCAL slot take one line
+        assert_eq!(lines[5], "005-0FE  000  NOP ; 250 words");
+        assert_eq!(lines[6], "0FF  000  NOP ; OSCCAL");
+        assert_eq!(lines.len(), 7);
+    }
+
+    #[test]
+    pub fn test_flash_replaces_every_word() {
+        let mut program_memory = ProgramMemory::new();
+        let mut first = sample_program();
+        first[0x0FF] = u12::new(0xC70); // MOVLW 0x70, the OSCCAL word
+        first[0x1FF] = u12::new(0xC71);
+        program_memory.flash(first);
+        program_memory.push(u9::new(0x012));
+
+        program_memory.flash(sample_program());
+        assert!(program_memory.iter().zip(sample_program().iter()).all(|(word, expected)| word == expected));
+        // the OSCCAL slot is overwritten like any other word
+        assert_eq!(program_memory.fetch(u9::new(0x0FF)), u12::new(0x000));
+        assert_eq!(program_memory.fetch(u9::new(0x1FF)), u12::new(0x000));
+        assert!(program_memory.is_flashed(u9::new(0x1FF)));
+        // the stack is not touched
+        assert_eq!(program_memory.stack()[0], u9::new(0x012));
+    }
+
+    #[test]
+    pub fn test_blank_memory_runs_as_nop() {
+        use crate::pic::{PIC10F200, PipelinedTuringMachine, Programmable};
+        use crate::program_memory::BLANK_WORD;
+
+        assert_eq!(ProgramMemory::new().fetch(u9::new(0x080)), BLANK_WORD);
+
+        // MOVLW 0x5A, GOTO 0x080 into memory flash_words left erased
+        let mut pic = PIC10F200::new();
+        pic.program_words(u9::new(0x000), &[u12::new(0xC5A), u12::new(0xA80)]);
+        pic.power_on_initialize();
+        pic.run_cycles(20);
+        assert_eq!(pic.w_register(), 0x5A);
+
+        // an all ones word in the image is not blank, it runs as XORLW 0xFF
+        pic.program_words(u9::new(0x000), &[u12::new(0xC5A), u12::new(0xFFF)]);
+        pic.power_on_initialize();
+        pic.run_cycles(4);
+        assert_eq!(pic.w_register(), 0xA5);
+    }
+}
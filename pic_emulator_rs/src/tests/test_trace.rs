@@ -0,0 +1,120 @@
+#[cfg(test)]
+mod test {
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    use crate::nbitnumber::{u12, u9};
+    use crate::pic::{PICInstructionMnemonic, PIC10F200, PipelinedTuringMachine, Programmable};
+    use crate::tests::sample_program;
+
+    // counts 0x11 up ten times
+    fn loop_program() -> [u12; 0x200] {
+        let mut program = [u12::new(0); 0x200];
+        program[0x000] = u12::new(0xC0A); // MOVLW 10
+        program[0x001] = u12::new(0x030); // MOVWF 0x10
+        program[0x002] = u12::new(0x2B1); // loop: INCF 0x11, F
+        program[0x003] = u12::new(0x2F0); // DECFSZ 0x10, F
+        program[0x004] = u12::new(0xA02); // GOTO loop
+        program[0x005] = u12::new(0x003); // SLEEP
+        program
+    }
+
+    #[test]
+    pub fn test_trace_hook_sees_executed_instructions() {
+        let addresses = Rc::new(RefCell::new(Vec::new()));
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+
+        let seen = addresses.clone();
+        pic.set_trace_hook(move |event| seen.borrow_mut().push((event.address.get(), event.w)));
+        pic.run_to_halt();
+
+        // the OSCCAL slot then the program, the empty first cycle is not traced
+        assert_eq!(
+            *addresses.borrow(),
+            vec![(0x0FF, 0x00), (0x000, 0x03), (0x001, 0x03), (0x002, 0x04), (0x003, 0x07), (0x004, 0x07)]
+        );
+    }
+
+    #[test]
+    pub fn test_profile_loop_counts() {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(loop_program());
+        pic.power_on_initialize();
+        pic.enable_profiler();
+        pic.run_to_halt();
+
+        let report = pic.profile_report();
+        let count = |address: u16| {
+            report.by_address.iter().find(|(at, _)| *at == u9::new(address)).map_or(0, |(_, count)| *count)
+        };
+        assert_eq!(count(0x002), 10);
+        assert_eq!(count(0x003), 10);
+        // the last DECFSZ skips the GOTO
+        assert_eq!(count(0x004), 9);
+        assert_eq!(count(0x005), 1);
+
+        assert_eq!(report.by_mnemonic[0], (PICInstructionMnemonic::DECFSZ, 10));
+        assert_eq!(report.by_mnemonic[1], (PICInstructionMnemonic::INCF, 10));
+        assert_eq!(report.by_mnemonic[2], (PICInstructionMnemonic::GOTO, 9));
+        assert_eq!(pic.peek(crate::nbitnumber::u5::new(0x11)), 10);
+    }
+
+    #[test]
+    pub fn test_profile_report_empty_when_disabled() {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(loop_program());
+        pic.power_on_initialize();
+        pic.run_to_halt();
+        assert!(pic.profile_report().by_address.is_empty());
+    }
+
+    #[test]
+    pub fn test_periodic_callback_fires_every_n_cycles() {
+        let fired = Rc::new(RefCell::new(Vec::new()));
+        let mut pic = PIC10F200::new();
+        pic.program_chip(loop_program());
+        pic.power_on_initialize();
+
+        let seen = fired.clone();
+        pic.set_periodic_callback(10, move |cycle| seen.borrow_mut().push(cycle));
+        pic.run_cycles(35);
+        assert_eq!(*fired.borrow(), vec![10, 20, 30]);
+
+        pic.clear_periodic_callback();
+        pic.run_cycles(10);
+        assert_eq!(fired.borrow().len(), 3);
+    }
+
+    #[test]
+    pub fn test_golden_trace_round_trip() {
+        use crate::trace::GoldenTrace;
+
+        let run = |program: [u12; 0x200]| {
+            let mut pic = PIC10F200::new();
+            pic.program_chip(program);
+            pic.power_on_initialize();
+            pic
+        };
+        let golden = run(sample_program()).record_golden_trace(100);
+        // the sample program sleeps after six instructions
+        assert_eq!(golden.records.len(), 6);
+        assert_eq!(golden.records[1].address, u9::new(0x000));
+
+        let path = std::env::temp_dir().join(format!("pic10_golden_trace_{}.txt", std::process::id()));
+        golden.save(&path).unwrap();
+        let loaded = GoldenTrace::load(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(loaded, golden);
+        assert_eq!(run(sample_program()).verify_golden_trace(&loaded), Ok(()));
+
+        // a different literal in the first MOVLW shows up at its instruction
+        let mut changed = sample_program();
+        changed[0x000] = u12::new(changed[0x000].get() ^ 0x001);
+        let mismatch = run(changed).verify_golden_trace(&loaded).unwrap_err();
+        assert_eq!(mismatch.index, 1);
+        assert_eq!(mismatch.expected, Some(golden.records[1]));
+        assert!(GoldenTrace::from_text("100\n3 000 C03\n").is_none());
+    }
+}
@@ -0,0 +1,71 @@
+#[cfg(test)]
+mod test {
+    use crate::nbitnumber::u9;
+    use crate::pic::{HaltReason, PIC10F200, PipelinedTuringMachine, Programmable};
+    use crate::tests::sample_program;
+    use crate::tui::TuiState;
+
+    fn state() -> TuiState {
+        let mut pic = PIC10F200::new();
+        pic.program_chip(sample_program());
+        pic.power_on_initialize();
+        TuiState::new(pic)
+    }
+
+    fn register(state: &TuiState, name: &str) -> u8 {
+        state.registers.iter().find(|(register, _)| register == name).unwrap().1
+    }
+
+    #[test]
+    pub fn test_state_updates_after_step() {
+        let mut state = state();
+        assert_eq!(state.current, u9::new(0xFF));
+        assert_eq!(state.status, "cycle 0");
+
+        // past the OSCCAL slot and through MOVLW 0x03
+        state.step();
+        state.step();
+        assert_eq!(state.current, u9::new(0x000));
+        assert!(state.listing.iter().any(|line| line.starts_with(">  000  C03  MOVLW 0x03")));
+
+        state.step();
+        assert_eq!(state.current, u9::new(0x001));
+        assert_eq!(register(&state, "W"), 0x03);
+        assert_eq!(state.status, "cycle 3");
+    }
+
+    #[test]
+    pub fn test_continue_stops_at_toggled_breakpoint() {
+        let mut state = state();
+        state.step();
+        state.step();
+        state.step();
+        state.step();
+        state.toggle_breakpoint();
+        assert!(state.listing.iter().any(|line| line.starts_with(">* 002")));
+
+        state.continue_running();
+        assert_eq!(state.pic.halt_reason(), Some(HaltReason::Sleep));
+        assert_eq!(register(&state, "W"), 0x07);
+        assert_eq!(register(&state, "0x10"), 0x03);
+    }
+
+    #[test]
+    pub fn test_registers_follow_the_device_map() {
+        use crate::config::DeviceConfig;
+        use crate::pic::PIC10F2Types;
+
+        let names = |device| {
+            let mut pic = PIC10F200::with_config(DeviceConfig::new(device));
+            pic.program_chip(sample_program());
+            pic.power_on_initialize();
+            TuiState::new(pic).registers.into_iter().map(|(name, _)| name).collect::<Vec<_>>()
+        };
+        let names_200 = names(PIC10F2Types::PIC10F200);
+        assert!(!names_200.iter().any(|name| name == "CMCON0" || name == "0x08"));
+        assert!(names_200.iter().any(|name| name == "0x10"));
+        let names_206 = names(PIC10F2Types::PIC10F206);
+        assert!(names_206.iter().any(|name| name == "CMCON0"));
+        assert!(names_206.iter().any(|name| name == "0x08"));
+    }
+}
@@ -0,0 +1,256 @@
+use alloc::boxed::Box;
+use alloc::collections::BTreeMap;
+use alloc::format;
+use alloc::rc::Rc;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+#[cfg(feature = "std")]
+use std::{fs, io, path::Path};
+
+use crate::data_memory::SpecialPurposeRegisters;
+use crate::nbitnumber::{u12, u9, NumberOperations};
+use crate::pic::{PICInstruction, PICInstructionMnemonic, PIC10F200};
+
+//One executed instruction, reported after it has run
+//flushed NOPs left by a branch are not reported as they were never fetched
+#[derive(Clone, Copy)]
+pub struct TraceEvent {
+    pub cycle: u64,
+    pub address: u9,
+    pub instruction: PICInstruction,
+    pub mnemonic: PICInstructionMnemonic,
+    pub w: u8,
+    pub status: u8,
+}
+
+pub type TraceHook = Box<dyn FnMut(&TraceEvent)>;
+
+//Called with the cycle count every N instruction cycles, e.g. to refresh a display
+pub type PeriodicCallback = Box<dyn FnMut(u64)>;
+
+//Execution counts gathered from the trace while the profiler is enabled
+#[derive(Default)]
+pub struct Profiler {
+    by_address: BTreeMap<u16, u64>,
+    by_mnemonic: BTreeMap<PICInstructionMnemonic, u64>,
+}
+
+impl Profiler {
+    fn record(&mut self, event: &TraceEvent) {
+        *self.by_address.entry(event.address.as_u16()).or_insert(0) += 1;
+        *self.by_mnemonic.entry(event.mnemonic).or_insert(0) += 1;
+    }
+}
+
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct ProfileReport {
+    //executions per program address, in address order
+    pub by_address: Vec<(u9, u64)>,
+    //executions per mnemonic, hottest first
+    pub by_mnemonic: Vec<(PICInstructionMnemonic, u64)>,
+}
+
+//The state a golden trace keeps for each executed instruction
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TraceRecord {
+    pub cycle: u64,
+    pub address: u9,
+    pub word: u12,
+    pub w: u8,
+    pub status: u8,
+}
+
+impl From<&TraceEvent> for TraceRecord {
+    fn from(event: &TraceEvent) -> Self {
+        TraceRecord {
+            cycle: event.cycle,
+            address: event.address,
+            word: event.instruction.instruction_raw,
+            w: event.w,
+            status: event.status,
+        }
+    }
+}
+
+//A recorded run to check later runs against, saved as one line per instruction
+//of cycle, address, word, W and STATUS with the cycle count run on the first line
+#[derive(Clone, PartialEq, Eq, Debug, Default)]
+pub struct GoldenTrace {
+    pub cycles: u64,
+    pub records: Vec<TraceRecord>,
+}
+
+//The first point where a run left its golden trace, found is None if the run ended early
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct TraceMismatch {
+    pub index: usize,
+    pub expected: Option<TraceRecord>,
+    pub found: Option<TraceRecord>,
+}
+
+impl fmt::Display for TraceMismatch {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "instruction {} differs from the golden trace, expected {:?}, found {:?}", self.index, self.expected, self.found)
+    }
+}
+
+impl core::error::Error for TraceMismatch {}
+
+impl GoldenTrace {
+    pub fn to_text(&self) -> String {
+        let mut text = format!("{}\n", self.cycles);
+        for record in &self.records {
+            text += &format!(
+                "{} {:03X} {:03X} {:02X} {:02X}\n",
+                record.cycle,
+                record.address.as_u16(),
+                record.word.as_u16(),
+                record.w,
+                record.status
+            );
+        }
+        text
+    }
+
+    //None if a line is not in the format to_text writes
+    pub fn from_text(text: &str) -> Option<GoldenTrace> {
+        let mut lines = text.lines();
+        let cycles = lines.next()?.trim().parse().ok()?;
+        let records = lines
+            .filter(|line| !line.trim().is_empty())
+            .map(|line| {
+                let fields: Vec<&str> = line.split_whitespace().collect();
+                let [cycle, address, word, w, status] = fields.as_slice() else {
+                    return None;
+                };
+                let hex = |field: &str| u16::from_str_radix(field, 16).ok();
+                Some(TraceRecord {
+                    cycle: cycle.parse().ok()?,
+                    address: u9::try_new(hex(address)?).ok()?,
+                    word: u12::try_new(hex(word)?).ok()?,
+                    w: u8::from_str_radix(w, 16).ok()?,
+                    status: u8::from_str_radix(status, 16).ok()?,
+                })
+            })
+            .collect::<Option<_>>()?;
+        Some(GoldenTrace { cycles, records })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        fs::write(path, self.to_text())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn load(path: impl AsRef<Path>) -> io::Result<GoldenTrace> {
+        let text = fs::read_to_string(path)?;
+        GoldenTrace::from_text(&text).ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "not a golden trace"))
+    }
+}
+
+impl PIC10F200 {
+    //called with every executed instruction, replaces any previous hook
+    pub fn set_trace_hook<F>(&mut self, hook: F)
+    where
+        F: FnMut(&TraceEvent) + 'static,
+    {
+        self.trace_hook = Some(Box::new(hook));
+    }
+
+    pub fn clear_trace_hook(&mut self) {
+        self.trace_hook = None;
+    }
+
+    //start counting executions, clearing any earlier counts
+    pub fn enable_profiler(&mut self) {
+        self.profiler = Some(Profiler::default());
+    }
+
+    pub fn disable_profiler(&mut self) {
+        self.profiler = None;
+    }
+
+    pub fn profile_report(&self) -> ProfileReport {
+        let Some(profiler) = &self.profiler else {
+            return ProfileReport::default();
+        };
+        let by_address = profiler.by_address
+            .iter()
+            .map(|(address, count)| (u9::new(*address), *count))
+            .collect();
+        let mut by_mnemonic: Vec<_> = profiler.by_mnemonic.iter().map(|(mnemonic, count)| (*mnemonic, *count)).collect();
+        by_mnemonic.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.name().cmp(b.0.name())));
+        ProfileReport { by_address, by_mnemonic }
+    }
+
+    //called after every cycles instruction cycles, counted from power on, replaces any previous callback
+    pub fn set_periodic_callback<F>(&mut self, cycles: u64, callback: F)
+    where
+        F: FnMut(u64) + 'static,
+    {
+        assert!(cycles > 0, "the periodic callback needs a period of at least one cycle");
+        self.periodic_callback = Some((cycles, Box::new(callback)));
+    }
+
+    pub fn clear_periodic_callback(&mut self) {
+        self.periodic_callback = None;
+    }
+
+    pub(crate) fn run_periodic_callback(&mut self) {
+        let cycles = self.cycles;
+        if let Some((period, callback)) = &mut self.periodic_callback {
+            if cycles.is_multiple_of(*period) {
+                callback(cycles);
+            }
+        }
+    }
+
+    //run for up to cycles instruction cycles keeping every executed instruction,
+    //the trace hook is used while recording and cleared afterwards
+    pub fn record_golden_trace(&mut self, cycles: u64) -> GoldenTrace {
+        let records = Rc::new(RefCell::new(Vec::new()));
+        let recorded = records.clone();
+        self.set_trace_hook(move |event| recorded.borrow_mut().push(TraceRecord::from(event)));
+        self.run_cycles(cycles);
+        self.clear_trace_hook();
+        let records = records.take();
+        GoldenTrace { cycles, records }
+    }
+
+    //run the same number of cycles as the golden trace and compare every executed instruction
+    pub fn verify_golden_trace(&mut self, golden: &GoldenTrace) -> Result<(), TraceMismatch> {
+        let run = self.record_golden_trace(golden.cycles);
+        let length = run.records.len().max(golden.records.len());
+        for index in 0..length {
+            let expected = golden.records.get(index).copied();
+            let found = run.records.get(index).copied();
+            if expected != found {
+                return Err(TraceMismatch { index, expected, found });
+            }
+        }
+        Ok(())
+    }
+
+    pub(crate) fn is_tracing(&self) -> bool {
+        self.trace_hook.is_some() || self.profiler.is_some()
+    }
+
+    pub(crate) fn trace(&mut self, address: u9, instruction: PICInstruction) {
+        let event = TraceEvent {
+            cycle: self.cycles,
+            address,
+            instruction,
+            mnemonic: instruction.decode_mnemonic(),
+            w: self.w_register,
+            status: self.data_memory.read(SpecialPurposeRegisters::STATUS.address()),
+        };
+        if let Some(hook) = &mut self.trace_hook {
+            hook(&event);
+        }
+        if let Some(profiler) = &mut self.profiler {
+            profiler.record(&event);
+        }
+    }
+}
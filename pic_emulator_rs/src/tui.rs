@@ -0,0 +1,139 @@
+use crate::data_memory::RegisterFile;
+use crate::disassembler::{disassemble, SymbolTable, SPECIAL_REGISTER_NAMES};
+use crate::nbitnumber::{u9, NumberOperations};
+use crate::pic::{PIC10F200, PipelinedTuringMachine, RUN_TO_HALT_CYCLE_LIMIT};
+
+//Lines of disassembly shown either side of the current instruction
+const LISTING_CONTEXT: u16 = 6;
+
+//Everything the front end draws, rebuilt from the PIC after every command so it can be
+//tested without a terminal, the terminal itself is behind the tui feature
+pub struct TuiState {
+    pub pic: PIC10F200,
+    //address of the instruction the next step executes
+    pub current: u9,
+    pub listing: Vec<String>,
+    pub registers: Vec<(String, u8)>,
+    pub status: String,
+}
+
+impl TuiState {
+    pub fn new(pic: PIC10F200) -> Self {
+        let mut state = TuiState {
+            pic,
+            current: u9::new(0),
+            listing: Vec::new(),
+            registers: Vec::new(),
+            status: String::new(),
+        };
+        state.refresh();
+        state
+    }
+
+    //run a single instruction cycle
+    pub fn step(&mut self) {
+        self.pic.resume();
+        self.pic.tick();
+        self.refresh();
+    }
+
+    //run until a breakpoint, watchpoint or halt
+    pub fn continue_running(&mut self) {
+        self.pic.resume();
+        self.pic.run_cycles(RUN_TO_HALT_CYCLE_LIMIT);
+        self.refresh();
+    }
+
+    pub fn toggle_breakpoint(&mut self) {
+        if self.pic.breakpoints().contains(&self.current) {
+            self.pic.remove_breakpoint(self.current);
+        } else {
+            self.pic.add_breakpoint(self.current);
+        }
+        self.refresh();
+    }
+
+    fn refresh(&mut self) {
+        let pipeline = self.pic.pipeline_state();
+        self.current = pipeline.execute_address.unwrap_or(pipeline.fetch_address);
+
+        let symbols = SymbolTable::new();
+        let breakpoints = self.pic.breakpoints();
+        let first = self.current.get().saturating_sub(LISTING_CONTEXT);
+        let last = (self.current.get() + LISTING_CONTEXT).min(self.pic.config().program_words - 1);
+        self.listing = (first..=last)
+            .map(|address| {
+                let address = u9::new(address);
+                let marker = if address == self.current { ">" } else { " " };
+                let breakpoint = if breakpoints.contains(&address) { "*" } else { " " };
+                let word = self.pic.program_memory.fetch(address);
+                format!("{}{} {:03X}  {:03X}  {}", marker, breakpoint, address.get(), word.get(), disassemble(word, Some(&symbols)))
+            })
+            .collect();
+
+        self.registers = vec![("W".to_string(), self.pic.w_register())];
+        //INDF is not a register of its own, the rest follow the device's memory map
+        for address in RegisterFile::implemented_addresses(self.pic.config()).into_iter().skip(1) {
+            let name = match SPECIAL_REGISTER_NAMES.get(address.as_usize()) {
+                Some(name) => name.to_string(),
+                None => format!("0x{:02X}", address.get()),
+            };
+            self.registers.push((name, self.pic.peek(address)));
+        }
+
+        self.status = match self.pic.halt_reason() {
+            Some(reason) => format!("cycle {}  halted: {:?}", self.pic.cycles(), reason),
+            None => format!("cycle {}", self.pic.cycles()),
+        };
+    }
+}
+
+//Run the interactive front end until q is pressed
+//s steps one instruction cycle, c continues, b toggles a breakpoint on the current instruction
+#[cfg(feature = "tui")]
+pub fn run(pic: PIC10F200) -> std::io::Result<()> {
+    use ratatui::crossterm::event::{self, Event, KeyCode, KeyEventKind};
+
+    let mut state = TuiState::new(pic);
+    let mut terminal = ratatui::init();
+    let result = loop {
+        if let Err(error) = terminal.draw(|frame| draw(frame, &state)) {
+            break Err(error);
+        }
+        match event::read() {
+            Ok(Event::Key(key)) if key.kind == KeyEventKind::Press => match key.code {
+                KeyCode::Char('q') => break Ok(()),
+                KeyCode::Char('s') => state.step(),
+                KeyCode::Char('c') => state.continue_running(),
+                KeyCode::Char('b') => state.toggle_breakpoint(),
+                _ => {}
+            },
+            Ok(_) => {}
+            Err(error) => break Err(error),
+        }
+    };
+    ratatui::restore();
+    result
+}
+
+#[cfg(feature = "tui")]
+fn draw(frame: &mut ratatui::Frame, state: &TuiState) {
+    use ratatui::layout::{Constraint, Layout};
+    use ratatui::widgets::{Block, Paragraph};
+
+    let [main, status] = Layout::vertical([Constraint::Min(0), Constraint::Length(1)]).areas(frame.area());
+    let [program, registers] = Layout::horizontal([Constraint::Percentage(65), Constraint::Percentage(35)]).areas(main);
+
+    let listing = state.listing.join("\n");
+    frame.render_widget(Paragraph::new(listing).block(Block::bordered().title("Program")), program);
+
+    let registers_text = state.registers
+        .iter()
+        .map(|(name, value)| format!("{:<7} {:02X}", name, value))
+        .collect::<Vec<_>>()
+        .join("\n");
+    frame.render_widget(Paragraph::new(registers_text).block(Block::bordered().title("Registers")), registers);
+
+    let help = format!("{}  [s]tep [c]ontinue [b]reakpoint [q]uit", state.status);
+    frame.render_widget(Paragraph::new(help), status);
+}